@@ -102,7 +102,6 @@ fn bench(c: &mut Criterion) {
                 index: 0,
                 enabled: true,
                 amount: UnitInterval::MAX,
-                target: paw::modx::mod_pack::ModTarget::default(),
                 delay,
                 attack,
                 hold,
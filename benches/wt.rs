@@ -75,7 +75,7 @@ fn bench(c: &mut Criterion) {
         let group = group.sample_size(10_000);
 
         group.bench_function("lerp", |b| {
-            b.iter(|| props.lerp(black_box(0.1276438512323)))
+            b.iter(|| props.lerp(black_box(0.1276438512323), black_box(0.0091)))
         });
     }
 
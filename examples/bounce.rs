@@ -0,0 +1,77 @@
+use paw::{
+    daw::{
+        render::{NoteEvent, RenderLength},
+        Daw,
+    },
+    export::wav::{WavFormat, WavSpec, WavWriter},
+    midi::{event::MIDI_CHANNELS, note::Note, smf::read_smf},
+    param::f32::UnitInterval,
+    sample::time::SampleCount,
+    wavetable::synth::create_basic_wavetable_synth,
+};
+use std::env::temp_dir;
+
+const SAMPLE_RATE: u32 = 48_000;
+const VOICES: usize = 8;
+const LFOS: usize = 1;
+const ENVS: usize = 1;
+const OSCS: usize = 1;
+
+/// Renders a MIDI file passed as the first argument, with a synth per MIDI channel, or a simple arpeggio
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut daw = Daw::<{ MIDI_CHANNELS as usize }, 1, 0>::new(SAMPLE_RATE);
+
+    (0..MIDI_CHANNELS).for_each(|_| {
+        daw.rack_mut()
+            .push_instrument(Box::new(create_basic_wavetable_synth::<
+                VOICES,
+                LFOS,
+                ENVS,
+                OSCS,
+            >(SAMPLE_RATE)))
+            .unwrap();
+    });
+    daw.rack_mut().set_active(0);
+
+    let events = if let Some(path) = std::env::args().nth(1) {
+        read_smf(&std::fs::read(path)?, SAMPLE_RATE).map_err(|err| format!("{err:?}"))?
+    } else {
+        let beat = SAMPLE_RATE / 2;
+        let mut events = [Note::C4, Note::E4, Note::G4, Note::C5]
+            .into_iter()
+            .enumerate()
+            .flat_map(|(index, note)| {
+                let tick = index as u32 * beat;
+                [
+                    NoteEvent::on(tick, note, UnitInterval::MAX),
+                    NoteEvent::off(tick + beat, note, UnitInterval::MAX),
+                ]
+            })
+            .collect::<Vec<_>>();
+        events.sort_by_key(|event| event.tick);
+        events
+    };
+
+    let mut writer = WavWriter::new(Vec::new(), WavSpec::new(SAMPLE_RATE, WavFormat::Pcm24))
+        .map_err(|err| format!("{err:?}"))?;
+
+    let frames = daw
+        .render(
+            &events,
+            RenderLength::UntilSilent {
+                tail_limit: SampleCount::from_secs(2, SAMPLE_RATE),
+            },
+            &mut writer,
+        )
+        .map_err(|err| format!("{err:?}"))?;
+
+    let bytes = writer.finalize().map_err(|err| format!("{err:?}"))?;
+
+    let mut path = temp_dir();
+    path.push("paw-bounce.wav");
+    std::fs::write(&path, bytes)?;
+
+    println!("Rendered {frames} frames into {}", path.display());
+
+    Ok(())
+}
@@ -1,9 +1,13 @@
 use paw::{
     daw::channel_rack::Instrument,
     midi::{event::MidiEventListener, note::Note},
-    modx::{lfo::LfoWaveform, mod_pack::ModTarget},
+    modx::{
+        lfo::LfoWaveform,
+        matrix::{ModRoute, ModSource},
+        mod_pack::ModTarget,
+    },
     osc::clock::{Clock, Freq},
-    param::f32::UnitInterval,
+    param::f32::{SignedUnitInterval, UnitInterval},
     wavetable::synth::create_basic_wavetable_synth,
 };
 use plotters::{
@@ -29,9 +33,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     lfo0.enabled = true;
     lfo0.amount = UnitInterval::MAX;
     lfo0.freq = Freq::Hz(5);
-    lfo0.target = ModTarget::GlobalPitch;
     lfo0.waveform = LfoWaveform::Sine;
 
+    synth
+        .matrix_mut()
+        .push(ModRoute::new(
+            ModSource::Lfo(0),
+            ModTarget::GlobalPitch,
+            SignedUnitInterval::MAX,
+        ))
+        .unwrap();
+
     let root = SVGBackend::new(OUTPUT_FILE, (1080, 720)).into_drawing_area();
 
     const NOTE: Note = Note::A4;
@@ -2,13 +2,16 @@ use nannou::prelude::*;
 use nannou_audio::{self as audio, Buffer};
 use nannou_egui::Egui;
 use paw::{
+    fx::{delay::DelayFx, dist::DistFx},
     midi::note::Note,
     param::{f32::UnitInterval, ui::EguiComponent as _},
+    wavetable::synth::create_basic_wavetable_synth,
 };
 use std::sync::{Arc, Mutex};
 
 type Daw = paw::daw::Daw<8, 8, 4>;
 const SAMPLE_RATE: u32 = 48_000;
+const DELAY_SIZE: usize = SAMPLE_RATE as usize;
 
 fn note_from_nannou_key(key: nannou::event::Key) -> Result<Note, ()> {
     match key {
@@ -163,7 +166,27 @@ fn model(app: &App) -> Model {
 
     let audio_host = audio::Host::new();
 
-    let daw = Daw::new(SAMPLE_RATE);
+    let mut daw = Daw::new(SAMPLE_RATE);
+
+    let channel = daw
+        .rack_mut()
+        .push_instrument(Box::new(create_basic_wavetable_synth::<8, 2, 2, 2>(
+            SAMPLE_RATE,
+        )))
+        .unwrap();
+    daw.rack_mut().set_active(channel);
+
+    // Dist -> Delay chain on the first mixer track
+    let track = daw.mixer_mut().track_mut(0);
+    track
+        .push_effect(Box::new(DistFx::new()))
+        .ok()
+        .expect("Free fx slot");
+    track
+        .push_effect(Box::new(DelayFx::<DELAY_SIZE>::new(SAMPLE_RATE)))
+        .ok()
+        .expect("Free fx slot");
+
     let daw = Arc::new(Mutex::new(daw));
 
     let audio_model = AudioModel {
@@ -15,7 +15,8 @@ const FX_SLOTS: usize = 4;
 fn main() {
     let mut daw = Daw::<CHANNEL_RACK_SIZE, MIXER_SIZE, FX_SLOTS>::new(SAMPLE_RATE);
 
-    daw.rack_mut()
+    let channel = daw
+        .rack_mut()
         .push_instrument(Box::new(create_basic_wavetable_synth::<
             VOICES,
             LFOS,
@@ -23,6 +24,7 @@ fn main() {
             OSCS,
         >(SAMPLE_RATE)))
         .unwrap();
+    daw.rack_mut().set_active(channel);
 
     let mut buffer = [Frame::zero(); SAMPLE_RATE as usize];
     for note in Note::each() {
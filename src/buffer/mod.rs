@@ -1,3 +1,5 @@
+use alloc::{boxed::Box, vec};
+
 pub trait RingIndex {
     type Output;
 
@@ -45,3 +47,82 @@ impl<T> RingIndexMut for &mut [T] {
         &mut self[index % self.len()]
     }
 }
+
+/// Ring buffer of samples with fractional (linearly interpolated) reads. Delays are measured from the last pushed sample, so `read(0.0)` is the last pushed sample and the longest available delay is `SIZE - 1`. Samples live on the heap, so that long lines are never built on the stack.
+#[derive(Debug, Clone)]
+pub struct DelayLine<const SIZE: usize> {
+    buffer: Box<[f32; SIZE]>,
+    write_index: usize,
+}
+
+impl<const SIZE: usize> Default for DelayLine<SIZE> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize> DelayLine<SIZE> {
+    pub const MAX_DELAY: usize = SIZE - 1;
+
+    pub fn new() -> Self {
+        Self {
+            // Through a vector, as `Box::new([0.0; SIZE])` may copy the array from the stack
+            buffer: vec![0.0; SIZE].into_boxed_slice().try_into().unwrap(),
+            write_index: 0,
+        }
+    }
+
+    #[inline]
+    pub fn push(&mut self, sample: f32) {
+        *self.buffer.ring_index_mut(self.write_index) = sample;
+        self.write_index = (self.write_index + 1) % SIZE;
+    }
+
+    /// Read sample delayed by whole count of samples
+    #[inline]
+    pub fn tap(&self, delay: usize) -> f32 {
+        *self
+            .buffer
+            .ring_index(self.write_index + SIZE - 1 - delay % SIZE)
+    }
+
+    /// Read sample delayed by fractional count of samples, clamped to [`DelayLine::MAX_DELAY`]
+    #[inline]
+    pub fn read(&self, delay: f32) -> f32 {
+        let delay = delay.clamp(0.0, Self::MAX_DELAY as f32);
+        let whole = delay as usize;
+        let fract = delay - whole as f32;
+
+        let newer = self.tap(whole);
+
+        if fract > 0.0 {
+            newer + (self.tap(whole + 1) - newer) * fract
+        } else {
+            newer
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.buffer.fill(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DelayLine;
+
+    #[test]
+    fn delay_line_fractional_read() {
+        let mut line = DelayLine::<4>::new();
+
+        (1..=6).for_each(|sample| line.push(sample as f32));
+
+        assert_eq!(line.tap(0), 6.0);
+        assert_eq!(line.tap(3), 3.0);
+        assert_eq!(line.read(1.25), 4.75);
+        // Clamped to the longest delay
+        assert_eq!(line.read(10.0), 3.0);
+    }
+}
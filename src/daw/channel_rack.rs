@@ -41,7 +41,10 @@ impl crate::param::ui::EguiComponent<(usize, bool, bool, usize, Clock), bool> fo
         let mut toggle_active = false;
 
         ui.horizontal(|ui| {
-            ui.add(egui::DragValue::new(&mut self.mixer_track).clamp_range(0..=mixer_size));
+            ui.add(
+                egui::DragValue::new(&mut self.mixer_track)
+                    .clamp_range(0..=mixer_size.saturating_sub(1)),
+            );
 
             toggle_active = egui::Button::new(self.instrument.name())
                 .fill(if playing {
@@ -85,6 +88,41 @@ impl MidiEventListener for RackChannel {
     ) {
         self.instrument.note_off(clock, note, velocity);
     }
+
+    #[inline]
+    fn pitch_bend(&mut self, clock: &Clock, bend: crate::param::f32::SignedUnitInterval) {
+        self.instrument.pitch_bend(clock, bend);
+    }
+
+    #[inline]
+    fn control_change(
+        &mut self,
+        clock: &Clock,
+        controller: u8,
+        value: crate::param::f32::UnitInterval,
+    ) {
+        self.instrument.control_change(clock, controller, value);
+    }
+
+    #[inline]
+    fn channel_pressure(&mut self, clock: &Clock, pressure: crate::param::f32::UnitInterval) {
+        self.instrument.channel_pressure(clock, pressure);
+    }
+
+    #[inline]
+    fn poly_pressure(
+        &mut self,
+        clock: &Clock,
+        note: crate::midi::note::Note,
+        pressure: crate::param::f32::UnitInterval,
+    ) {
+        self.instrument.poly_pressure(clock, note, pressure);
+    }
+
+    #[inline]
+    fn program_change(&mut self, clock: &Clock, program: u8) {
+        self.instrument.program_change(clock, program);
+    }
 }
 
 impl RackChannel {
@@ -100,6 +138,17 @@ impl RackChannel {
         self.instrument.as_mut()
     }
 
+    #[inline]
+    pub fn mixer_track(&self) -> usize {
+        self.mixer_track
+    }
+
+    /// Route the channel to the mixer track, which must exist in the mixer
+    #[inline]
+    pub fn set_mixer_track(&mut self, mixer_track: usize) {
+        self.mixer_track = mixer_track;
+    }
+
     #[inline]
     pub fn tick(&mut self, clock: &Clock) -> TrackOutput {
         TrackOutput::new(self.mixer_track, self.instrument.tick(clock))
@@ -113,8 +162,11 @@ impl RackChannel {
 
 pub struct ChannelRack<const SIZE: usize> {
     channels: [Option<RackChannel>; SIZE],
+    /// Channel playing live input, see the [`MidiEventListener`] implementation
     active: Option<usize>,
     is_active_playing: bool,
+    /// Channels live notes were triggered on, so that they're released there even if the active channel changes meanwhile
+    live_notes: [Option<usize>; 128],
 }
 
 #[cfg(feature = "egui")]
@@ -157,6 +209,7 @@ impl<const SIZE: usize> crate::param::ui::EguiComponent<(usize, Clock)> for Chan
     }
 }
 
+/// Live input played on the active channel only, other channels are played by [`ChannelRack::note_on_channel`]
 impl<const SIZE: usize> MidiEventListener for ChannelRack<SIZE> {
     #[inline]
     fn note_on(
@@ -165,8 +218,16 @@ impl<const SIZE: usize> MidiEventListener for ChannelRack<SIZE> {
         note: crate::midi::note::Note,
         velocity: crate::param::f32::UnitInterval,
     ) {
-        self.iter_channels_mut()
-            .for_each(|channel| channel.note_on(clock, note, velocity));
+        let Some(active) = self.active else {
+            return;
+        };
+
+        if let Some(previous) = self.live_notes[note as usize].replace(active) {
+            if previous != active {
+                self.note_off_channel(clock, previous, note, crate::param::f32::UnitInterval::MIN);
+            }
+        }
+        self.note_on_channel(clock, active, note, velocity);
     }
 
     #[inline]
@@ -176,8 +237,54 @@ impl<const SIZE: usize> MidiEventListener for ChannelRack<SIZE> {
         note: crate::midi::note::Note,
         velocity: crate::param::f32::UnitInterval,
     ) {
-        self.iter_channels_mut()
-            .for_each(|channel| channel.note_off(clock, note, velocity));
+        if let Some(channel) = self.live_notes[note as usize].take() {
+            self.note_off_channel(clock, channel, note, velocity);
+        }
+    }
+
+    #[inline]
+    fn pitch_bend(&mut self, clock: &Clock, bend: crate::param::f32::SignedUnitInterval) {
+        if let Some(channel) = self.active_channel_mut() {
+            channel.pitch_bend(clock, bend);
+        }
+    }
+
+    #[inline]
+    fn control_change(
+        &mut self,
+        clock: &Clock,
+        controller: u8,
+        value: crate::param::f32::UnitInterval,
+    ) {
+        if let Some(channel) = self.active_channel_mut() {
+            channel.control_change(clock, controller, value);
+        }
+    }
+
+    #[inline]
+    fn channel_pressure(&mut self, clock: &Clock, pressure: crate::param::f32::UnitInterval) {
+        if let Some(channel) = self.active_channel_mut() {
+            channel.channel_pressure(clock, pressure);
+        }
+    }
+
+    #[inline]
+    fn poly_pressure(
+        &mut self,
+        clock: &Clock,
+        note: crate::midi::note::Note,
+        pressure: crate::param::f32::UnitInterval,
+    ) {
+        if let Some(channel) = self.active_channel_mut() {
+            channel.poly_pressure(clock, note, pressure);
+        }
+    }
+
+    #[inline]
+    fn program_change(&mut self, clock: &Clock, program: u8) {
+        if let Some(channel) = self.active_channel_mut() {
+            channel.program_change(clock, program);
+        }
     }
 }
 
@@ -187,6 +294,7 @@ impl<const SIZE: usize> ChannelRack<SIZE> {
             channels: [const { None }; SIZE],
             active: None,
             is_active_playing: false,
+            live_notes: [None; 128],
         }
     }
 
@@ -214,13 +322,15 @@ impl<const SIZE: usize> ChannelRack<SIZE> {
         self.active = Some(active);
     }
 
-    // TODO: This should not tick but process active channel or played in sequencer
-    // pub fn tick<const MIXER_SIZE: usize>(&mut self, clock: &Clock) -> UnmixedOutput<MIXER_SIZE> {
-    //     self.channels
-    //         .iter_mut()
-    //         .filter_map(|inst| inst.as_mut().map(|inst| inst.tick(clock)))
-    //         .fold(UnmixedOutput::zero(), |output, channel| output + channel)
-    // }
+    #[inline]
+    pub fn channel_mut(&mut self, channel: usize) -> Option<&mut RackChannel> {
+        self.channels.get_mut(channel).and_then(Option::as_mut)
+    }
+
+    #[inline]
+    fn active_channel_mut(&mut self) -> Option<&mut RackChannel> {
+        self.active.and_then(|active| self.channel_mut(active))
+    }
 
     #[inline]
     fn iter_channels_mut(&mut self) -> impl Iterator<Item = &mut RackChannel> {
@@ -229,39 +339,72 @@ impl<const SIZE: usize> ChannelRack<SIZE> {
             .filter_map(|channel| channel.as_mut())
     }
 
+    /// Play the note on a single channel only
     #[inline]
-    pub fn tick_active<const MIXER_SIZE: usize>(
+    pub fn note_on_channel(
         &mut self,
         clock: &Clock,
-    ) -> UnmixedOutput<MIXER_SIZE> {
-        self.active
-            .and_then(|active| {
-                self.channels[active].as_mut().map(|channel| {
-                    self.is_active_playing = true;
-                    channel.tick(clock).into()
-                })
-            })
-            .unwrap_or_else(|| {
-                self.is_active_playing = false;
-                UnmixedOutput::zero()
+        channel: usize,
+        note: crate::midi::note::Note,
+        velocity: crate::param::f32::UnitInterval,
+    ) {
+        if let Some(rack_channel) = self.channels.get_mut(channel).and_then(Option::as_mut) {
+            rack_channel.note_on(clock, note, velocity);
+        }
+    }
+
+    #[inline]
+    pub fn note_off_channel(
+        &mut self,
+        clock: &Clock,
+        channel: usize,
+        note: crate::midi::note::Note,
+        velocity: crate::param::f32::UnitInterval,
+    ) {
+        if let Some(rack_channel) = self.channels.get_mut(channel).and_then(Option::as_mut) {
+            rack_channel.note_off(clock, note, velocity);
+        }
+    }
+
+    /// Tick all channels summing channels which share a mixer track
+    #[inline]
+    pub fn tick<const MIXER_SIZE: usize>(&mut self, clock: &Clock) -> UnmixedOutput<MIXER_SIZE> {
+        self.is_active_playing = self.is_active_present();
+
+        self.iter_channels_mut()
+            .fold(UnmixedOutput::zero(), |output, channel| {
+                output + UnmixedOutput::from(channel.tick(clock))
             })
     }
 
+    /// Render all channels into the buffers of their mixer tracks, summing channels which share a track. `scratch` is the block length, track buffers are overwritten up to it.
     #[inline]
-    pub fn process_buffer_active<const MIXER_SIZE: usize>(
+    pub fn process_buffer<const BLOCK_SIZE: usize>(
         &mut self,
         clock: &Clock,
-        buffer: &mut [Frame],
-    ) -> Option<usize> {
-        self.active.and_then(|active| {
-            if let Some(channel) = self.channels[active].as_mut() {
-                self.is_active_playing = true;
-                channel.process_buffer(clock, buffer);
-                Some(channel.mixer_track)
-            } else {
-                self.is_active_playing = false;
-                None
-            }
-        })
+        scratch: &mut [Frame],
+        tracks: &mut [[Frame; BLOCK_SIZE]],
+    ) {
+        self.is_active_playing = self.is_active_present();
+
+        let len = scratch.len();
+        tracks
+            .iter_mut()
+            .for_each(|track| track[..len].fill(Frame::zero()));
+
+        self.iter_channels_mut().for_each(|channel| {
+            channel.process_buffer(clock, scratch);
+
+            tracks[channel.mixer_track][..len]
+                .iter_mut()
+                .zip(scratch.iter())
+                .for_each(|(mix, &frame)| *mix = *mix + frame);
+        });
+    }
+
+    #[inline]
+    fn is_active_present(&self) -> bool {
+        self.active
+            .is_some_and(|active| self.channels[active].is_some())
     }
 }
@@ -0,0 +1,271 @@
+use crate::{
+    osc::clock::Clock,
+    sample::{time::SampleCount, Frame},
+};
+#[allow(unused)]
+use num_traits::Float as _;
+
+/// Absolute sample value above which the signal is clipped
+pub const METER_CLIP_LEVEL: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MeterParams {
+    /// Time the peak stays at its maximum before it starts to decay
+    pub hold: SampleCount,
+    /// Peak fall rate in decibels per second
+    pub decay: f32,
+    /// RMS is measured over consecutive windows of this length
+    pub rms_window: SampleCount,
+}
+
+impl MeterParams {
+    pub const fn new(sample_rate: u32) -> Self {
+        Self {
+            hold: SampleCount::from_millis(1_000, sample_rate),
+            decay: 20.0,
+            rms_window: SampleCount::from_millis(300, sample_rate),
+        }
+    }
+
+    /// Per sample factor of the peak decay
+    #[inline]
+    fn decay_factor(&self, sample_rate: u32) -> f32 {
+        10f32.powf(-self.decay / 20.0 / sample_rate as f32)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ChannelMeter {
+    peak: f32,
+    /// Samples left until the peak starts to decay
+    hold: u32,
+    squares: f32,
+    counted: u32,
+    rms: f32,
+}
+
+impl ChannelMeter {
+    const fn new() -> Self {
+        Self {
+            peak: 0.0,
+            hold: 0,
+            squares: 0.0,
+            counted: 0,
+            rms: 0.0,
+        }
+    }
+
+    #[inline]
+    fn tick(&mut self, sample: f32, params: &MeterParams, decay_factor: f32) {
+        let level = sample.abs();
+
+        if level >= self.peak {
+            self.peak = level;
+            self.hold = params.hold.inner();
+        } else if self.hold > 0 {
+            self.hold -= 1;
+        } else {
+            self.peak = (self.peak * decay_factor).max(level);
+        }
+
+        self.squares += sample * sample;
+        self.counted += 1;
+        if self.counted >= params.rms_window.inner().max(1) {
+            self.rms = (self.squares / self.counted as f32).sqrt();
+            self.squares = 0.0;
+            self.counted = 0;
+        }
+    }
+}
+
+/// Levels of a stereo signal measured by [`Meter`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeterReading {
+    /// Held and decaying absolute peak
+    pub peak: Frame,
+    /// RMS of the last complete window
+    pub rms: Frame,
+    /// The signal exceeded [`METER_CLIP_LEVEL`] since the last [`Meter::reset_clip`]
+    pub clipped: bool,
+}
+
+/// Peak, RMS and clip meter. Metering does not allocate, so it runs on the audio thread while the readings are taken by the UI.
+#[derive(Debug, Clone, Copy)]
+pub struct Meter {
+    channels: [ChannelMeter; 2],
+    clipped: bool,
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Meter {
+    pub const fn new() -> Self {
+        Self {
+            channels: [const { ChannelMeter::new() }; 2],
+            clipped: false,
+        }
+    }
+
+    #[inline]
+    pub fn tick(&mut self, clock: &Clock, input: Frame, params: &MeterParams) {
+        let decay_factor = params.decay_factor(clock.sample_rate);
+        self.measure(input, params, decay_factor);
+    }
+
+    #[inline]
+    pub fn process_buffer(&mut self, clock: &Clock, buffer: &[Frame], params: &MeterParams) {
+        let decay_factor = params.decay_factor(clock.sample_rate);
+        buffer
+            .iter()
+            .for_each(|&frame| self.measure(frame, params, decay_factor));
+    }
+
+    #[inline]
+    fn measure(&mut self, input: Frame, params: &MeterParams, decay_factor: f32) {
+        let [left, right] = &mut self.channels;
+        left.tick(*input.left(), params, decay_factor);
+        right.tick(*input.right(), params, decay_factor);
+
+        self.clipped |= input
+            .into_iter()
+            .any(|sample| sample.abs() > METER_CLIP_LEVEL);
+    }
+
+    #[inline]
+    pub fn reading(&self) -> MeterReading {
+        let [left, right] = &self.channels;
+
+        MeterReading {
+            peak: Frame::stereo(left.peak, right.peak),
+            rms: Frame::stereo(left.rms, right.rms),
+            clipped: self.clipped,
+        }
+    }
+
+    /// Clear the sticky clip indicator
+    #[inline]
+    pub fn reset_clip(&mut self) {
+        self.clipped = false;
+    }
+
+    /// Draw a bar per channel with RMS filled and the peak as a line on the [`crate::param::f32::DbLevel`] fader scale. Clicking the clip indicator resets it.
+    #[cfg(feature = "egui")]
+    pub fn egui(&mut self, ui: &mut egui::Ui) {
+        use crate::param::f32::DbLevel;
+
+        ui.ctx().request_repaint();
+
+        let reading = self.reading();
+        let position = |level: f32| {
+            DbLevel::from_db(20.0 * level.max(f32::MIN_POSITIVE).log10())
+                .position()
+                .inner()
+        };
+
+        ui.vertical(|ui| {
+            let (clip_rect, clip) =
+                ui.allocate_exact_size(egui::vec2(14.0, 6.0), egui::Sense::click());
+            ui.painter().rect_filled(
+                clip_rect,
+                1.0,
+                if reading.clipped {
+                    egui::Color32::RED
+                } else {
+                    egui::Color32::from_gray(40)
+                },
+            );
+            if clip.clicked() {
+                self.reset_clip();
+            }
+
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(14.0, 150.0), egui::Sense::hover());
+            let painter = ui.painter().with_clip_rect(rect);
+            painter.rect_filled(rect, 0.0, egui::Color32::from_gray(20));
+
+            [
+                (*reading.rms.left(), *reading.peak.left()),
+                (*reading.rms.right(), *reading.peak.right()),
+            ]
+            .into_iter()
+            .enumerate()
+            .for_each(|(channel, (rms, peak))| {
+                let left = rect.left() + channel as f32 * rect.width() / 2.0;
+                let right = left + rect.width() / 2.0 - 1.0;
+                let y = |level| rect.bottom() - position(level) * rect.height();
+
+                painter.rect_filled(
+                    egui::Rect::from_x_y_ranges(left..=right, y(rms)..=rect.bottom()),
+                    0.0,
+                    egui::Color32::from_rgb(60, 180, 80),
+                );
+                painter.hline(
+                    left..=right,
+                    y(peak),
+                    egui::Stroke::new(
+                        1.0,
+                        if peak > METER_CLIP_LEVEL {
+                            egui::Color32::RED
+                        } else {
+                            egui::Color32::from_gray(220)
+                        },
+                    ),
+                );
+            });
+
+            // 0 dB mark
+            painter.hline(
+                rect.x_range(),
+                rect.bottom() - DbLevel::UNITY.position().inner() * rect.height(),
+                egui::Stroke::new(1.0, egui::Color32::from_gray(90)),
+            );
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Meter, MeterParams};
+    use crate::{
+        osc::clock::Clock,
+        sample::{time::SampleCount, Frame},
+    };
+
+    #[test]
+    fn peak_rms_clip() {
+        let clock = Clock::zero(1_000);
+        let params = MeterParams {
+            hold: SampleCount::new(10),
+            decay: 20_000.0,
+            rms_window: SampleCount::new(4),
+        };
+        let mut meter = Meter::new();
+
+        meter.process_buffer(&clock, &[Frame::stereo(0.5, -1.5)], &params);
+        let reading = meter.reading();
+        assert_eq!(reading.peak, Frame::stereo(0.5, 1.5));
+        assert!(reading.clipped);
+
+        // The peak is held, RMS is updated once the window is complete
+        meter.process_buffer(&clock, &[Frame::zero(); 3], &params);
+        let reading = meter.reading();
+        assert_eq!(reading.peak, Frame::stereo(0.5, 1.5));
+        assert_eq!(reading.rms, Frame::stereo(0.25, 0.75));
+
+        // After the hold the peak falls by 20 dB per sample
+        (0..7).for_each(|_| meter.tick(&clock, Frame::zero(), &params));
+        assert_eq!(meter.reading().peak, Frame::stereo(0.5, 1.5));
+        meter.tick(&clock, Frame::zero(), &params);
+        let peak = meter.reading().peak;
+        assert!((peak.left() - 0.05).abs() < 1e-6, "{peak:?}");
+
+        // Clip indicator is sticky
+        assert!(meter.reading().clipped);
+        meter.reset_clip();
+        assert!(!meter.reading().clipped);
+        assert_eq!(meter.reading().rms, Frame::zero());
+    }
+}
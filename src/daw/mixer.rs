@@ -2,8 +2,12 @@ use core::{iter::Sum, ops::Add};
 
 use alloc::boxed::Box;
 
+use super::meter::{Meter, MeterParams};
 use crate::{
-    fx::Fx, midi::event::MidiEventListener, osc::clock::Clock, param::f32::UnitInterval,
+    fx::Fx,
+    midi::event::MidiEventListener,
+    osc::clock::Clock,
+    param::f32::{DbLevel, SignedUnitInterval, UnitInterval},
     sample::Frame,
 };
 
@@ -91,33 +95,87 @@ impl<const SIZE: usize> UnmixedOutput<SIZE> {
 }
 
 pub struct MixerTrack<const FX_SLOTS: usize> {
-    // TODO: Disable fx
-    // TODO: Mute
-    // TODO: Panning
-    // TODO!: Decibel level
-    pub(super) level: UnitInterval,
+    pub(super) level: DbLevel,
+    pub(super) pan: SignedUnitInterval,
+    pub(super) muted: bool,
+    /// Soloed tracks silence all the tracks which are not soloed
+    pub(super) solo: bool,
     pub(super) effects: [Option<Box<dyn Fx>>; FX_SLOTS],
+    /// Bypassed effect slots pass the input through
+    pub(super) bypassed: [bool; FX_SLOTS],
+    /// Post-fader output levels
+    pub(super) meter: Meter,
 }
 
+/// Track label in the mixer, `None` for the master track
 #[cfg(feature = "egui")]
-impl<const FX_SLOTS: usize> crate::param::ui::EguiComponent<(usize, Clock)>
+impl<const FX_SLOTS: usize> crate::param::ui::EguiComponent<(Option<usize>, Clock)>
     for MixerTrack<FX_SLOTS>
 {
-    fn egui(&mut self, ui: &mut egui::Ui, (index, clock): (usize, Clock)) {
+    fn egui(&mut self, ui: &mut egui::Ui, (index, clock): (Option<usize>, Clock)) {
         ui.vertical(|ui| {
             ui.set_max_width(50.0);
             ui.vertical_centered(|ui| {
-                ui.label(format!("{index}"));
+                ui.label(
+                    index
+                        .map(|index| format!("{index}"))
+                        .unwrap_or_else(|| "Master".into()),
+                );
             });
 
-            ui.vertical_centered(|ui| {
-                ui.add(self.level.widget().vertical());
+            ui.horizontal(|ui| {
+                ui.toggle_value(&mut self.muted, "M");
+                if index.is_some() {
+                    ui.toggle_value(&mut self.solo, "S");
+                }
             });
 
-            // TODO: Render only focused effect
-            self.iter_effects_mut().for_each(|fx| {
-                fx.egui(ui, (clock,));
+            ui.add(self.pan.widget().show_value(false));
+
+            ui.horizontal(|ui| {
+                ui.add(self.level.widget().vertical().show_value(false));
+                self.meter.egui(ui);
             });
+            ui.label(format!("{:.1}", self.level.db()));
+
+            // TODO: Render only focused effect
+            self.effects
+                .iter_mut()
+                .zip(self.bypassed.iter_mut())
+                .filter_map(|(fx, bypassed)| fx.as_mut().map(|fx| (fx, bypassed)))
+                .for_each(|(fx, bypassed)| {
+                    ui.checkbox(bypassed, "Bypass");
+                    fx.egui(ui, (clock,));
+                });
+
+            // TODO: Plugin manager
+            if self.effects.iter().any(Option::is_none) {
+                ui.menu_button("Add fx", |ui| {
+                    const CHORUS_SIZE: usize = 4_096;
+                    const DELAY_SIZE: usize = 65_536;
+
+                    let fx: Option<Box<dyn Fx>> = if ui.button("Chorus").clicked() {
+                        Some(Box::new(crate::fx::chorus::ChorusFx::<CHORUS_SIZE>::new(
+                            clock.sample_rate,
+                        )))
+                    } else if ui.button("Delay").clicked() {
+                        Some(Box::new(crate::fx::delay::DelayFx::<DELAY_SIZE>::new(
+                            clock.sample_rate,
+                        )))
+                    } else if ui.button("Dist").clicked() {
+                        Some(Box::new(crate::fx::dist::DistFx::new()))
+                    } else if ui.button("Filter").clicked() {
+                        Some(Box::new(crate::fx::filter::FilterFx::new()))
+                    } else {
+                        None
+                    };
+
+                    if let Some(fx) = fx {
+                        let _ = self.push_effect(fx);
+                        ui.close_menu();
+                    }
+                });
+            }
         });
     }
 }
@@ -139,40 +197,171 @@ impl<const FX_SLOTS: usize> MidiEventListener for MixerTrack<FX_SLOTS> {
 impl<const FX_SLOTS: usize> MixerTrack<FX_SLOTS> {
     const fn new() -> Self {
         Self {
-            level: UnitInterval::MAX,
+            level: DbLevel::UNITY,
+            pan: SignedUnitInterval::EQUILIBRIUM,
+            muted: false,
+            solo: false,
             effects: [const { None }; FX_SLOTS],
+            bypassed: [false; FX_SLOTS],
+            meter: Meter::new(),
         }
     }
 
     #[inline]
-    pub fn level_mut(&mut self) -> &mut UnitInterval {
+    pub fn level_mut(&mut self) -> &mut DbLevel {
         &mut self.level
     }
 
+    #[inline]
+    pub fn meter(&self) -> &Meter {
+        &self.meter
+    }
+
+    #[inline]
+    pub fn meter_mut(&mut self) -> &mut Meter {
+        &mut self.meter
+    }
+
+    #[inline]
+    pub fn pan_mut(&mut self) -> &mut SignedUnitInterval {
+        &mut self.pan
+    }
+
+    #[inline]
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    #[inline]
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    #[inline]
+    pub fn is_solo(&self) -> bool {
+        self.solo
+    }
+
+    #[inline]
+    pub fn set_solo(&mut self, solo: bool) {
+        self.solo = solo;
+    }
+
+    #[inline]
+    pub fn is_bypassed(&self, slot: usize) -> bool {
+        self.bypassed.get(slot).copied().unwrap_or(false)
+    }
+
+    /// Pass the input of the effect slot through instead of processing it
+    #[inline]
+    pub fn set_bypassed(&mut self, slot: usize, bypassed: bool) {
+        if let Some(slot) = self.bypassed.get_mut(slot) {
+            *slot = bypassed;
+        }
+    }
+
+    /// Put effect into the first free slot returning slot index, or give the effect back if all slots are occupied
+    pub fn push_effect(&mut self, fx: Box<dyn Fx>) -> Result<usize, Box<dyn Fx>> {
+        if let Some(slot) = self.effects.iter().position(Option::is_none) {
+            self.effects[slot] = Some(fx);
+            self.bypassed[slot] = false;
+
+            Ok(slot)
+        } else {
+            Err(fx)
+        }
+    }
+
     #[inline]
     pub fn iter_effects_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Fx>> {
         self.effects.iter_mut().filter_map(|fx| fx.as_mut())
     }
 
+    #[inline]
+    fn iter_active_effects_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn Fx>> {
+        self.effects
+            .iter_mut()
+            .zip(self.bypassed)
+            .filter(|(_, bypassed)| !bypassed)
+            .filter_map(|(fx, _)| fx.as_mut())
+    }
+
+    #[inline]
+    fn fader(&self, input: Frame) -> Frame {
+        (input * self.level.gain()).panned(self.pan)
+    }
+
+    /// Run the input through the effects, the output is the pre-fader signal
+    #[inline]
+    fn process(&mut self, clock: &Clock, input: Frame) -> Frame {
+        self.iter_active_effects_mut()
+            .fold(input, |input, fx| fx.tick(clock, input))
+    }
+
+    #[inline]
+    fn process_buffer(&mut self, clock: &Clock, buffer: &mut [Frame]) {
+        self.iter_active_effects_mut().for_each(|fx| {
+            fx.process_buffer(clock, buffer);
+        });
+    }
+
     #[inline]
     fn mix(&mut self, clock: &Clock, input: Frame) -> Frame {
-        self.effects.iter_mut().fold(input, |input, fx| {
-            fx.as_mut().map(|fx| fx.tick(clock, input)).unwrap_or(input)
-        }) * self.level.inner()
+        let output = self.process(clock, input);
+
+        self.fader(output)
     }
 
     #[inline]
     fn mix_buffer(&mut self, clock: &Clock, buffer: &mut [Frame]) {
-        self.effects.iter_mut().for_each(|effect| {
-            effect.as_mut().map(|fx| {
-                fx.process_buffer(clock, buffer);
-            });
-        });
+        self.process_buffer(clock, buffer);
+
+        buffer
+            .iter_mut()
+            .for_each(|frame| *frame = self.fader(*frame));
+    }
+}
+
+/// Signal sent from one mixer track into the input of another one, e.g. to share a reverb on a return track
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackSend {
+    pub level: DbLevel,
+    /// Send the signal after effects but before level and pan of the source track, which also keeps sending when the source is muted
+    pub pre_fader: bool,
+}
+
+impl Default for TrackSend {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
+impl TrackSend {
+    pub const fn new() -> Self {
+        Self {
+            level: DbLevel::UNITY,
+            pre_fader: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendError {
+    /// No such track in the mixer
+    InvalidTrack(usize),
+    /// The send would feed the source track with its own output
+    Cycle,
+}
+
 pub struct Mixer<const SIZE: usize, const FX_SLOTS: usize> {
     pub(super) tracks: [MixerTrack<FX_SLOTS>; SIZE],
+    /// All the tracks are summed into the master track
+    pub(super) master: MixerTrack<FX_SLOTS>,
+    /// Sends by source and target track
+    sends: [[Option<TrackSend>; SIZE]; SIZE],
+    /// Order of track processing in which each track comes after all the tracks sending to it
+    order: [usize; SIZE],
+    meter_params: MeterParams,
 }
 
 #[cfg(feature = "egui")]
@@ -181,15 +370,16 @@ impl<const SIZE: usize, const FX_SLOTS: usize> crate::param::ui::EguiComponent
 {
     fn egui(&mut self, ui: &mut egui::Ui, params: crate::param::ui::DefaultUiParams) {
         ui.horizontal(|ui| {
-            self.tracks
-                .iter_mut()
-                .enumerate()
-                .for_each(|(index, track)| {
-                    if index > 0 {
-                        ui.separator();
-                    }
-                    track.egui(ui, (index, params.clock))
+            self.master.egui(ui, (None, params.clock));
+
+            (0..SIZE).for_each(|index| {
+                ui.separator();
+
+                ui.vertical(|ui| {
+                    self.tracks[index].egui(ui, (Some(index), params.clock));
+                    self.egui_sends(ui, index);
                 });
+            });
         });
     }
 }
@@ -199,6 +389,7 @@ impl<const SIZE: usize, const FX_SLOTS: usize> MidiEventListener for Mixer<SIZE,
     fn note_on(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
         self.tracks
             .iter_mut()
+            .chain([&mut self.master])
             .for_each(|track| track.note_on(clock, note, velocity));
     }
 
@@ -206,36 +397,382 @@ impl<const SIZE: usize, const FX_SLOTS: usize> MidiEventListener for Mixer<SIZE,
     fn note_off(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
         self.tracks
             .iter_mut()
+            .chain([&mut self.master])
             .for_each(|track| track.note_off(clock, note, velocity));
     }
 }
 
 impl<const SIZE: usize, const FX_SLOTS: usize> Mixer<SIZE, FX_SLOTS> {
     #[inline]
-    pub const fn new() -> Self {
+    pub const fn new(sample_rate: u32) -> Self {
+        let mut order = [0; SIZE];
+        let mut index = 0;
+        while index < SIZE {
+            order[index] = index;
+            index += 1;
+        }
+
         Self {
             tracks: [const { MixerTrack::new() }; SIZE],
+            master: MixerTrack::new(),
+            sends: [[None; SIZE]; SIZE],
+            order,
+            meter_params: MeterParams::new(sample_rate),
         }
     }
 
+    #[inline]
+    pub fn track_mut(&mut self, track: usize) -> &mut MixerTrack<FX_SLOTS> {
+        &mut self.tracks[track]
+    }
+
+    #[inline]
+    pub fn master_mut(&mut self) -> &mut MixerTrack<FX_SLOTS> {
+        &mut self.master
+    }
+
     #[inline]
     pub fn iter_tracks_mut(&mut self) -> impl Iterator<Item = &mut MixerTrack<FX_SLOTS>> {
         self.tracks.iter_mut()
     }
 
-    // TODO: Master track
+    /// Metering settings of all the tracks
+    #[inline]
+    pub fn meter_params_mut(&mut self) -> &mut MeterParams {
+        &mut self.meter_params
+    }
+
+    #[inline]
+    pub fn send(&self, from: usize, to: usize) -> Option<&TrackSend> {
+        self.sends.get(from)?.get(to)?.as_ref()
+    }
+
+    /// Send level and mode can be changed in place, routing only by [`Mixer::set_send`]
+    #[inline]
+    pub fn send_mut(&mut self, from: usize, to: usize) -> Option<&mut TrackSend> {
+        self.sends.get_mut(from)?.get_mut(to)?.as_mut()
+    }
+
+    /// Send track `from` into track `to` replacing the existing send between them. Fails if track `to` already feeds track `from`, directly or through other tracks.
+    pub fn set_send(&mut self, from: usize, to: usize, send: TrackSend) -> Result<(), SendError> {
+        if let Some(track) = [from, to].into_iter().find(|&track| track >= SIZE) {
+            return Err(SendError::InvalidTrack(track));
+        }
+
+        if self.feeds(to, from) {
+            return Err(SendError::Cycle);
+        }
+
+        self.sends[from][to] = Some(send);
+        self.update_order();
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn remove_send(&mut self, from: usize, to: usize) -> Option<TrackSend> {
+        let send = self.sends.get_mut(from)?.get_mut(to)?.take();
+        self.update_order();
+        send
+    }
+
+    /// Signal of track `from` reaches track `to` through sends, a track feeds itself. Tracks out of range feed nothing.
+    pub fn feeds(&self, from: usize, to: usize) -> bool {
+        if from >= SIZE || to >= SIZE {
+            return false;
+        }
+        if from == to {
+            return true;
+        }
+
+        let mut visited = [false; SIZE];
+        let mut stack = [0; SIZE];
+        let mut len = 0;
+
+        visited[from] = true;
+        stack[0] = from;
+        len += 1;
+
+        while len > 0 {
+            len -= 1;
+            let track = stack[len];
+
+            for (target, send) in self.sends[track].iter().enumerate() {
+                if send.is_none() || visited[target] {
+                    continue;
+                }
+                if target == to {
+                    return true;
+                }
+
+                visited[target] = true;
+                stack[len] = target;
+                len += 1;
+            }
+        }
+
+        false
+    }
+
+    /// Topological sort of tracks by sends, keeping the index order where possible
+    fn update_order(&mut self) {
+        let mut inputs = [0usize; SIZE];
+        self.sends.iter().for_each(|sends| {
+            sends
+                .iter()
+                .zip(inputs.iter_mut())
+                .filter(|(send, _)| send.is_some())
+                .for_each(|(_, inputs)| *inputs += 1);
+        });
+
+        let mut done = [false; SIZE];
+        for position in 0..SIZE {
+            // Routing is acyclic, so there is always a track with all inputs processed
+            let Some(track) = (0..SIZE).find(|&track| !done[track] && inputs[track] == 0) else {
+                break;
+            };
+
+            done[track] = true;
+            self.order[position] = track;
+
+            self.sends[track]
+                .iter()
+                .zip(inputs.iter_mut())
+                .filter(|(send, _)| send.is_some())
+                .for_each(|(_, inputs)| *inputs -= 1);
+        }
+    }
+
+    /// Tracks that are heard. Soloed tracks silence the others except the tracks they send to.
+    #[inline]
+    fn audible(&self) -> [bool; SIZE] {
+        let any_solo = self.tracks.iter().any(|track| track.solo);
+
+        core::array::from_fn(|index| {
+            let track = &self.tracks[index];
+            let soloed = track.solo
+                || self
+                    .tracks
+                    .iter()
+                    .zip(self.sends.iter())
+                    .any(|(source, sends)| source.solo && sends[index].is_some());
+
+            !track.muted && (soloed || !any_solo)
+        })
+    }
+
+    /// Mix tracks in routing order passing the sends, and run the sum through the master track. Silenced tracks are processed anyway to keep their effects running.
     #[inline]
     pub fn mix(&mut self, clock: &Clock, input: UnmixedOutput<SIZE>) -> Frame {
-        self.tracks
-            .iter_mut()
-            .zip(input.tracks)
-            .fold(Frame::zero(), |mix, (track, input)| {
-                mix + track.mix(clock, input)
-            })
+        let audible = self.audible();
+        let mut inputs = input.tracks;
+
+        let mixed = self.order.iter().fold(Frame::zero(), |mix, &index| {
+            let track = &mut self.tracks[index];
+            let pre_fader = track.process(clock, inputs[index]);
+            let post_fader = track.fader(pre_fader);
+            track.meter.tick(clock, post_fader, &self.meter_params);
+
+            self.sends[index]
+                .iter()
+                .zip(inputs.iter_mut())
+                .for_each(|(send, input)| {
+                    if let Some(send) = send {
+                        if send.pre_fader || audible[index] {
+                            let output = if send.pre_fader {
+                                pre_fader
+                            } else {
+                                post_fader
+                            };
+                            *input = *input + output * send.level.gain();
+                        }
+                    }
+                });
+
+            if audible[index] {
+                mix + post_fader
+            } else {
+                mix
+            }
+        });
+
+        self.master_output(clock, mixed)
+    }
+
+    /// Same as [`Mixer::mix`] for buffers, summing tracks into `output`. Track buffers are processed in place up to the `output` length.
+    #[inline]
+    pub fn mix_buffers<const BLOCK_SIZE: usize>(
+        &mut self,
+        clock: &Clock,
+        tracks: &mut [[Frame; BLOCK_SIZE]; SIZE],
+        output: &mut [Frame],
+    ) {
+        let audible = self.audible();
+        let len = output.len();
+        output.fill(Frame::zero());
+
+        self.order.iter().for_each(|&index| {
+            let track = &mut self.tracks[index];
+            track.process_buffer(clock, &mut tracks[index][..len]);
+
+            let send = |tracks: &mut [[Frame; BLOCK_SIZE]; SIZE], pre_fader: bool| {
+                self.sends[index]
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(target, send)| send.map(|send| (target, send)))
+                    .filter(|(_, send)| send.pre_fader == pre_fader)
+                    .for_each(|(target, send)| {
+                        (0..len).for_each(|frame| {
+                            tracks[target][frame] =
+                                tracks[target][frame] + tracks[index][frame] * send.level.gain();
+                        });
+                    });
+            };
+
+            send(tracks, true);
+
+            let buffer = &mut tracks[index][..len];
+            buffer
+                .iter_mut()
+                .for_each(|frame| *frame = track.fader(*frame));
+            track
+                .meter
+                .process_buffer(clock, buffer, &self.meter_params);
+
+            if audible[index] {
+                send(tracks, false);
+
+                output
+                    .iter_mut()
+                    .zip(tracks[index][..len].iter())
+                    .for_each(|(mix, &frame)| *mix = *mix + frame);
+            }
+        });
+
+        self.master.mix_buffer(clock, output);
+        if self.master.muted {
+            output.fill(Frame::zero());
+        }
+        self.master
+            .meter
+            .process_buffer(clock, output, &self.meter_params);
     }
 
     #[inline]
-    pub fn mix_channel_buffer(&mut self, clock: &Clock, track: usize, buffer: &mut [Frame]) {
-        self.tracks[track].mix_buffer(clock, buffer);
+    fn master_output(&mut self, clock: &Clock, input: Frame) -> Frame {
+        let output = self.master.mix(clock, input);
+        let output = if self.master.muted {
+            Frame::zero()
+        } else {
+            output
+        };
+        self.master.meter.tick(clock, output, &self.meter_params);
+
+        output
+    }
+
+    #[cfg(feature = "egui")]
+    fn egui_sends(&mut self, ui: &mut egui::Ui, from: usize) {
+        (0..SIZE).for_each(|to| {
+            let Some(send) = self.sends[from][to].as_mut() else {
+                return;
+            };
+
+            ui.horizontal(|ui| {
+                ui.label(format!("> {to}"));
+                ui.toggle_value(&mut send.pre_fader, "Pre");
+            });
+            ui.add(send.level.widget().show_value(false));
+
+            if ui.button("Remove").clicked() {
+                self.remove_send(from, to);
+            }
+        });
+
+        // Only targets which don't feed the track back are offered
+        if (0..SIZE).any(|to| self.sends[from][to].is_none() && !self.feeds(to, from)) {
+            ui.menu_button("Send", |ui| {
+                (0..SIZE).for_each(|to| {
+                    if self.sends[from][to].is_none()
+                        && !self.feeds(to, from)
+                        && ui.button(format!("{to}")).clicked()
+                    {
+                        let _ = self.set_send(from, to, TrackSend::new());
+                        ui.close_menu();
+                    }
+                });
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mixer, SendError, TrackOutput, TrackSend, UnmixedOutput};
+    use crate::{
+        osc::clock::Clock,
+        param::f32::{DbLevel, SignedUnitInterval},
+        sample::Frame,
+    };
+
+    #[test]
+    fn mute_solo_pan() {
+        let clock = Clock::zero(48_000);
+        let mut mixer = Mixer::<2, 0>::new(48_000);
+        let input = || {
+            UnmixedOutput::from(TrackOutput::new(0, Frame::mono(1.0)))
+                + UnmixedOutput::from(TrackOutput::new(1, Frame::mono(0.5)))
+        };
+
+        assert_eq!(mixer.mix(&clock, input()), Frame::mono(1.5));
+
+        mixer.track_mut(0).set_solo(true);
+        assert_eq!(mixer.mix(&clock, input()), Frame::mono(1.0));
+
+        // Muted track is silent even if soloed
+        mixer.track_mut(0).set_muted(true);
+        assert_eq!(mixer.mix(&clock, input()), Frame::zero());
+
+        mixer.track_mut(0).set_solo(false);
+        *mixer.track_mut(1).pan_mut() = SignedUnitInterval::MIN;
+        let output = mixer.mix(&clock, input());
+        assert_eq!(output, Frame::stereo(0.5 * core::f32::consts::SQRT_2, 0.0));
+
+        *mixer.master_mut().level_mut() = DbLevel::SILENT;
+        assert_eq!(mixer.mix(&clock, input()), Frame::zero());
+    }
+
+    #[test]
+    fn sends() {
+        let clock = Clock::zero(48_000);
+        let mut mixer = Mixer::<3, 0>::new(48_000);
+        let input = || UnmixedOutput::from(TrackOutput::new(0, Frame::mono(1.0)));
+
+        // Track 0 feeds track 2 through track 1
+        let half = TrackSend {
+            level: DbLevel::from_db(-6.0),
+            pre_fader: false,
+        };
+        assert_eq!(mixer.set_send(1, 2, TrackSend::new()), Ok(()));
+        assert_eq!(mixer.set_send(0, 1, half), Ok(()));
+        assert_eq!(mixer.set_send(2, 0, half), Err(SendError::Cycle));
+        assert_eq!(mixer.set_send(1, 1, half), Err(SendError::Cycle));
+        assert_eq!(mixer.set_send(0, 3, half), Err(SendError::InvalidTrack(3)));
+        assert!(mixer.feeds(0, 2));
+        assert!(!mixer.feeds(2, 0));
+        assert!(!mixer.feeds(3, 0));
+        assert!(!mixer.feeds(3, 3));
+
+        let gain = half.level.gain();
+        assert_eq!(mixer.mix(&clock, input()), Frame::mono(1.0 + gain + gain));
+
+        // Pre-fader send ignores the level and mute of the source
+        mixer.send_mut(0, 1).unwrap().pre_fader = true;
+        *mixer.track_mut(0).level_mut() = DbLevel::SILENT;
+        mixer.track_mut(0).set_muted(true);
+        assert_eq!(mixer.mix(&clock, input()), Frame::mono(gain + gain));
+
+        assert_eq!(mixer.remove_send(1, 2), Some(TrackSend::new()));
+        assert_eq!(mixer.set_send(2, 0, half), Ok(()));
     }
 }
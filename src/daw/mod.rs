@@ -1,13 +1,20 @@
 use crate::{
-    midi::event::MidiEventListener,
+    midi::event::{MidiChannelFilter, MidiEvent, MidiEventListener},
     osc::clock::{Clock, Tick},
     sample::Frame,
 };
 use channel_rack::ChannelRack;
 use mixer::Mixer;
+use pattern::Sequencer;
 
 pub mod channel_rack;
+pub mod meter;
 pub mod mixer;
+pub mod pattern;
+pub mod render;
+
+/// Length of per-track scratch buffers, [`Daw::process_buffer`] splits longer buffers into blocks of this size
+pub const DAW_BLOCK_SIZE: usize = 256;
 
 pub enum ClockSource {
     Internal,
@@ -18,6 +25,12 @@ pub struct Daw<const CHANNEL_RACK_SIZE: usize, const MIXER_SIZE: usize, const FX
     rack: ChannelRack<CHANNEL_RACK_SIZE>,
     mixer: Mixer<MIXER_SIZE, FX_SLOTS>,
     clock: Clock,
+    /// Channels of incoming MIDI events passed to [`Daw::midi_event`] which are played
+    midi_channel: MidiChannelFilter,
+    sequencer: Sequencer<CHANNEL_RACK_SIZE>,
+    /// Single channel output before it's summed into its track
+    channel_buffer: [Frame; DAW_BLOCK_SIZE],
+    track_buffers: [[Frame; DAW_BLOCK_SIZE]; MIXER_SIZE],
 }
 
 #[cfg(feature = "egui")]
@@ -27,6 +40,19 @@ impl<const CHANNEL_RACK_SIZE: usize, const MIXER_SIZE: usize, const FX_SLOTS: us
     fn egui(&mut self, ui: &mut ::egui::Ui, _: ()) {
         let params = crate::param::ui::DefaultUiParams { clock: self.clock };
 
+        egui::TopBottomPanel::bottom("Pattern").show_inside(ui, |ui| {
+            let playing = self.sequencer.is_playing();
+            if ui.button(if playing { "Stop" } else { "Play" }).clicked() {
+                if playing {
+                    self.stop_pattern();
+                } else {
+                    self.play_pattern();
+                }
+            }
+
+            self.sequencer.egui(ui, params);
+        });
+
         egui::SidePanel::left("Channel rack")
             .resizable(false)
             .show_inside(ui, |ui| {
@@ -56,6 +82,7 @@ impl<const CHANNEL_RACK_SIZE: usize, const MIXER_SIZE: usize, const FX_SLOTS: us
 impl<const CHANNEL_RACK_SIZE: usize, const MIXER_SIZE: usize, const FX_SLOTS: usize>
     Daw<CHANNEL_RACK_SIZE, MIXER_SIZE, FX_SLOTS>
 {
+    /// Play live input on the active rack channel
     #[inline]
     pub fn note_on(
         &mut self,
@@ -76,6 +103,84 @@ impl<const CHANNEL_RACK_SIZE: usize, const MIXER_SIZE: usize, const FX_SLOTS: us
         self.mixer.note_off(&self.clock, note, velocity);
     }
 
+    /// Play the note on a single rack channel only
+    #[inline]
+    pub fn channel_note_on(
+        &mut self,
+        channel: usize,
+        note: crate::midi::note::Note,
+        velocity: crate::param::f32::UnitInterval,
+    ) {
+        self.rack
+            .note_on_channel(&self.clock, channel, note, velocity);
+        self.mixer.note_on(&self.clock, note, velocity);
+    }
+
+    #[inline]
+    pub fn channel_note_off(
+        &mut self,
+        channel: usize,
+        note: crate::midi::note::Note,
+        velocity: crate::param::f32::UnitInterval,
+    ) {
+        self.rack
+            .note_off_channel(&self.clock, channel, note, velocity);
+        self.mixer.note_off(&self.clock, note, velocity);
+    }
+
+    /// Play an incoming MIDI event, e.g. parsed with [`crate::midi::parser::MidiParser`], on the active rack channel. Events of channels not accepted by the channel filter are ignored.
+    #[inline]
+    pub fn midi_event(&mut self, event: &MidiEvent) {
+        if !self.midi_channel.accepts(event) {
+            return;
+        }
+
+        match *event {
+            MidiEvent::NoteOn { note, velocity, .. } => self.note_on(note, velocity),
+            MidiEvent::NoteOff { note, velocity, .. } => self.note_off(note, velocity),
+            MidiEvent::PitchBend { bend, .. } => self.rack.pitch_bend(&self.clock, bend),
+            MidiEvent::ControlChange {
+                controller, value, ..
+            } => self.rack.control_change(&self.clock, controller, value),
+            MidiEvent::ChannelPressure { pressure, .. } => {
+                self.rack.channel_pressure(&self.clock, pressure)
+            }
+            MidiEvent::PolyPressure { note, pressure, .. } => {
+                self.rack.poly_pressure(&self.clock, note, pressure)
+            }
+            MidiEvent::ProgramChange { program, .. } => {
+                self.rack.program_change(&self.clock, program)
+            }
+            _ => {}
+        }
+    }
+
+    #[inline]
+    pub fn midi_channel(&self) -> MidiChannelFilter {
+        self.midi_channel
+    }
+
+    #[inline]
+    pub fn set_midi_channel(&mut self, midi_channel: MidiChannelFilter) {
+        self.midi_channel = midi_channel;
+    }
+
+    /// Start playing the pattern of the sequencer from the current tick
+    #[inline]
+    pub fn play_pattern(&mut self) {
+        self.sequencer.play(&self.clock);
+    }
+
+    #[inline]
+    pub fn stop_pattern(&mut self) {
+        self.sequencer.stop(&self.clock, &mut self.rack);
+    }
+
+    #[inline(always)]
+    pub fn sequencer_mut(&mut self) -> &mut Sequencer<CHANNEL_RACK_SIZE> {
+        &mut self.sequencer
+    }
+
     #[inline(always)]
     pub fn clock(&self) -> Clock {
         self.clock
@@ -88,8 +193,12 @@ impl<const CHANNEL_RACK_SIZE: usize, const MIXER_SIZE: usize, const FX_SLOTS: us
     pub fn new(sample_rate: u32) -> Self {
         Self {
             rack: ChannelRack::new(),
-            mixer: Mixer::new(),
+            mixer: Mixer::new(sample_rate),
             clock: Clock::zero(sample_rate),
+            midi_channel: MidiChannelFilter::default(),
+            sequencer: Sequencer::new(),
+            channel_buffer: [Frame::zero(); DAW_BLOCK_SIZE],
+            track_buffers: [[Frame::zero(); DAW_BLOCK_SIZE]; MIXER_SIZE],
         }
     }
 
@@ -119,8 +228,8 @@ impl<const CHANNEL_RACK_SIZE: usize, const MIXER_SIZE: usize, const FX_SLOTS: us
 
     #[inline]
     fn tick_inner(&mut self) -> Frame {
-        let output = self.rack.tick_active(&self.clock);
-        // TODO: Sequencer mode instead of just playing active or merge active channel mode and sequencer mode.
+        self.sequencer.tick(&self.clock, &mut self.rack);
+        let output = self.rack.tick(&self.clock);
 
         let mixed = self.mixer.mix(&self.clock, output);
 
@@ -128,35 +237,198 @@ impl<const CHANNEL_RACK_SIZE: usize, const MIXER_SIZE: usize, const FX_SLOTS: us
     }
 
     /// Recommended instead of ticking. Processes a buffer at a time. This reduces overhead of `Box<dyn Instrument>` and `Box<dyn Fx>` as well as other values referencing during sample-by-sample processing with `tick*` methods. The usage of `process_buffer` does not guarantee that each DAW component will not use sample-by-sample method but avoids expensive values referencing while opening the door for compiler optimizations and caching.
+    ///
+    /// The output is the same as of ticking. Every channel is rendered into the buffer of its mixer track, tracks are mixed and summed into `buffer`. The buffer is processed in blocks of at most [`DAW_BLOCK_SIZE`], split at sequencer steps so notes are triggered on exact ticks.
     #[inline]
     pub fn process_buffer(&mut self, buffer: &mut [Frame]) {
-        let track = self
-            .rack
-            .process_buffer_active::<MIXER_SIZE>(&self.clock, buffer);
+        let mut rest = buffer;
+
+        while !rest.is_empty() {
+            self.sequencer.tick(&self.clock, &mut self.rack);
+
+            let len = self.block_len(rest.len());
+
+            let (block, next) = rest.split_at_mut(len);
 
-        track.map(|track| self.mixer.mix_channel_buffer(&self.clock, track, buffer));
+            self.rack.process_buffer(
+                &self.clock,
+                &mut self.channel_buffer[..len],
+                &mut self.track_buffers,
+            );
+            self.mixer
+                .mix_buffers(&self.clock, &mut self.track_buffers, block);
 
-        self.clock.tick_for_buffer(buffer.len() as Tick);
+            self.clock.tick_for_buffer(len as Tick);
+            rest = next;
+        }
+    }
+
+    /// Frames until the next sequencer event, at most `max_len` and [`DAW_BLOCK_SIZE`]. Ticks wrap like in the sequencer.
+    #[inline]
+    fn block_len(&self, max_len: usize) -> usize {
+        self.sequencer
+            .next_event(&self.clock)
+            .map(|next| next.wrapping_sub(self.clock.tick).max(1) as usize)
+            .unwrap_or(usize::MAX)
+            .min(DAW_BLOCK_SIZE)
+            .min(max_len)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{daw::Daw, osc::clock::Tick, sample::Frame};
+    use super::pattern::Step;
+    use crate::{
+        daw::{channel_rack::ChannelRack, mixer::TrackSend, Daw, DAW_BLOCK_SIZE},
+        fx::{delay::DelayFx, dist::DistFx},
+        midi::{event::MidiEventListener, note::Note},
+        osc::clock::{Clock, Tick},
+        param::f32::{DbLevel, SignedUnitInterval, UnitInterval},
+        sample::Frame,
+        wavetable::synth::create_basic_wavetable_synth,
+    };
+    use alloc::{boxed::Box, vec::Vec};
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn daw() -> Daw<3, 2, 1> {
+        let mut daw = Daw::new(SAMPLE_RATE);
+        (0..3).for_each(|_| {
+            daw.rack_mut()
+                .push_instrument(Box::new(create_basic_wavetable_synth::<4, 0, 1, 1>(
+                    SAMPLE_RATE,
+                )))
+                .unwrap();
+        });
+
+        // Channels 0 and 1 share the first track, channel 2 plays through the second one which also sends into the first
+        daw.rack_mut().channel_mut(2).unwrap().set_mixer_track(1);
+        *daw.mixer_mut().track_mut(0).level_mut() = DbLevel::from_db(-6.0);
+        *daw.mixer_mut().track_mut(1).pan_mut() = SignedUnitInterval::new(-0.5);
+        daw.mixer_mut()
+            .set_send(
+                1,
+                0,
+                TrackSend {
+                    level: DbLevel::from_db(-12.0),
+                    pre_fader: false,
+                },
+            )
+            .unwrap();
+        assert!(daw
+            .mixer_mut()
+            .track_mut(1)
+            .push_effect(Box::new(DelayFx::<4_096>::new(SAMPLE_RATE)))
+            .is_ok());
+        assert!(daw
+            .mixer_mut()
+            .master_mut()
+            .push_effect(Box::new(DistFx::new()))
+            .is_ok());
+
+        daw.channel_note_on(0, Note::A4, UnitInterval::MAX);
+        daw.channel_note_on(2, Note::E5, UnitInterval::MAX);
+
+        let pattern = daw.sequencer_mut().pattern_mut();
+        pattern.swing = UnitInterval::new(0.3);
+        *pattern.step_mut(1, 0).unwrap() = Some(Step::new(Note::C5));
+        *pattern.step_mut(1, 1).unwrap() = Some(Step::new(Note::G5));
+        daw.play_pattern();
+
+        daw
+    }
 
     #[test]
     fn process_buffer_tick_equal() {
-        const SAMPLE_RATE: u32 = 48_000;
-
         let mut buffer = [Frame::zero(); 1024];
 
-        let mut daw = Daw::<1, 1, 0>::new(SAMPLE_RATE);
+        let mut empty = Daw::<1, 1, 0>::new(SAMPLE_RATE);
 
-        daw.process_buffer(&mut buffer);
+        empty.process_buffer(&mut buffer);
 
         assert!(buffer
             .iter()
             .enumerate()
-            .all(|(index, sample)| { *sample == daw.tick_external(index as Tick) }));
+            .all(|(index, sample)| { *sample == empty.tick_external(index as Tick) }));
+
+        // All channels mixed through their tracks, buffers are not aligned to blocks and steps
+        let mut buffer = [Frame::zero(); 20_000];
+        let mut daw = daw();
+        buffer
+            .chunks_mut(1_000)
+            .for_each(|chunk| daw.process_buffer(chunk));
+
+        let mut daw = self::daw();
+        let ticked = (0..buffer.len())
+            .map(|_| daw.tick_internal())
+            .collect::<Vec<_>>();
+
+        assert!(buffer.iter().any(|frame| *frame != Frame::zero()));
+        assert_eq!(&buffer[..], &ticked[..]);
+    }
+
+    #[test]
+    fn process_buffer_across_tick_wrap() {
+        let daw = || {
+            let mut daw = self::daw();
+            daw.stop_pattern();
+            daw.clock.set(Tick::MAX - 99);
+            daw.play_pattern();
+            daw
+        };
+
+        // The first step's gate ends after the wrap, blocks are not cut short by it
+        let mut processed = daw();
+        processed.sequencer.tick(&processed.clock, &mut processed.rack);
+        assert_eq!(processed.block_len(usize::MAX), DAW_BLOCK_SIZE);
+
+        let mut buffer = [Frame::zero(); 10_000];
+        daw().process_buffer(&mut buffer);
+
+        let mut ticked = daw();
+        let ticked = (0..buffer.len())
+            .map(|_| ticked.tick_internal())
+            .collect::<Vec<_>>();
+
+        assert!(buffer.iter().any(|frame| *frame != Frame::zero()));
+        assert_eq!(&buffer[..], &ticked[..]);
+    }
+
+    #[test]
+    fn live_notes_on_active_channel() {
+        let mut rack = ChannelRack::<2>::new();
+        (0..2).for_each(|_| {
+            rack.push_instrument(Box::new(create_basic_wavetable_synth::<4, 0, 1, 1>(
+                SAMPLE_RATE,
+            )))
+            .unwrap();
+        });
+        rack.channel_mut(1).unwrap().set_mixer_track(1);
+
+        // Tracks which are not silent during the next block
+        fn sounding(rack: &mut ChannelRack<2>, clock: &mut Clock) -> [bool; 2] {
+            let mut scratch = [Frame::zero(); 256];
+            let mut tracks = [[Frame::zero(); 256]; 2];
+            rack.process_buffer(clock, &mut scratch, &mut tracks);
+            clock.tick_for_buffer(256);
+            tracks.map(|track| track.iter().any(|frame| *frame != Frame::zero()))
+        }
+
+        let mut clock = Clock::zero(SAMPLE_RATE);
+
+        // Without an active channel live input is not played
+        rack.note_on(&clock, Note::A4, UnitInterval::MAX);
+        assert_eq!(sounding(&mut rack, &mut clock), [false, false]);
+
+        rack.set_active(1);
+        rack.note_on(&clock, Note::C5, UnitInterval::MAX);
+        assert_eq!(sounding(&mut rack, &mut clock), [false, true]);
+
+        // The note is released on the channel it was played on even though another one is active now, the release is shorter than a block
+        rack.set_active(0);
+        rack.note_on(&clock, Note::E5, UnitInterval::MAX);
+        rack.note_off(&clock, Note::C5, UnitInterval::MAX);
+        sounding(&mut rack, &mut clock);
+        assert_eq!(sounding(&mut rack, &mut clock), [true, false]);
     }
 }
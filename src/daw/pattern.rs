@@ -0,0 +1,525 @@
+use super::channel_rack::ChannelRack;
+use crate::{
+    midi::note::Note,
+    osc::clock::{Clock, Tick},
+    param::f32::UnitInterval,
+    sample::time::NoteDivision,
+};
+#[allow(unused)]
+use num_traits::Float as _;
+
+/// Maximum count of steps in a pattern
+pub const PATTERN_MAX_STEPS: usize = 64;
+
+/// Count of steps new patterns have
+pub const PATTERN_DEFAULT_STEPS: usize = 16;
+
+/// Single cell of a channel step grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    pub note: Note,
+    pub velocity: UnitInterval,
+    /// Length of the note relative to the step length
+    pub gate: UnitInterval,
+    /// Chance of the step to be played on each pass
+    pub probability: UnitInterval,
+    /// Hold the note until the next step's note starts, so that mono and legato instruments glide into it
+    pub slide: bool,
+}
+
+impl Step {
+    pub const fn new(note: Note) -> Self {
+        Self {
+            note,
+            velocity: UnitInterval::MAX,
+            gate: UnitInterval::EQUILIBRIUM,
+            probability: UnitInterval::MAX,
+            slide: false,
+        }
+    }
+}
+
+/// Step grids for each rack channel, played in a loop at the same tempo
+#[derive(Debug, Clone)]
+pub struct Pattern<const CHANNELS: usize> {
+    grids: [[Option<Step>; PATTERN_MAX_STEPS]; CHANNELS],
+    steps: usize,
+    pub bpm: f32,
+    /// Length of a step
+    pub division: NoteDivision,
+    /// Delay of odd steps, at maximum they're played half a step late
+    pub swing: UnitInterval,
+}
+
+impl<const CHANNELS: usize> Default for Pattern<CHANNELS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const CHANNELS: usize> Pattern<CHANNELS> {
+    pub const fn new() -> Self {
+        Self {
+            grids: [[None; PATTERN_MAX_STEPS]; CHANNELS],
+            steps: PATTERN_DEFAULT_STEPS,
+            bpm: 120.0,
+            division: NoteDivision::straight(16),
+            swing: UnitInterval::MIN,
+        }
+    }
+
+    /// Count of steps in the loop
+    #[inline]
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    #[inline]
+    pub fn set_steps(&mut self, steps: usize) {
+        self.steps = steps.clamp(1, PATTERN_MAX_STEPS);
+    }
+
+    #[inline]
+    pub fn step(&self, channel: usize, step: usize) -> Option<&Step> {
+        self.grids.get(channel)?.get(step)?.as_ref()
+    }
+
+    #[inline]
+    pub fn step_mut(&mut self, channel: usize, step: usize) -> Option<&mut Option<Step>> {
+        self.grids.get_mut(channel)?.get_mut(step)
+    }
+
+    /// The channel has any steps in the loop
+    #[inline]
+    pub fn has_data(&self, channel: usize) -> bool {
+        self.grids
+            .get(channel)
+            .is_some_and(|grid| grid[..self.steps].iter().any(Option::is_some))
+    }
+
+    /// Length of a step in samples
+    #[inline]
+    fn step_len(&self, sample_rate: u32) -> f64 {
+        self.division.samples(self.bpm, sample_rate) as f64
+    }
+
+    #[inline]
+    fn swing_delay(&self, step: u64, step_len: f64) -> f64 {
+        if step % 2 == 1 {
+            self.swing.inner() as f64 * step_len / 2.0
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SoundingNote {
+    note: Note,
+    /// Tick to release the note at, `None` for slides released by the next step
+    off: Option<Tick>,
+}
+
+/// Plays a [`Pattern`] into rack channels in sync with the DAW clock
+#[derive(Debug, Clone)]
+pub struct Sequencer<const CHANNELS: usize> {
+    pattern: Pattern<CHANNELS>,
+    playing: bool,
+    /// Count of steps played since the start
+    next_step: u64,
+    /// Time of the next step without swing, in samples from the start
+    grid_time: f64,
+    /// Tick the playback started at
+    started_at: Tick,
+    sounding: [Option<SoundingNote>; CHANNELS],
+    /// Xorshift state deciding step probabilities
+    rng: u32,
+}
+
+impl<const CHANNELS: usize> Default for Sequencer<CHANNELS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const CHANNELS: usize> Sequencer<CHANNELS> {
+    const RNG_SEED: u32 = 0x9E37_79B9;
+
+    pub const fn new() -> Self {
+        Self {
+            pattern: Pattern::new(),
+            playing: false,
+            next_step: 0,
+            grid_time: 0.0,
+            started_at: 0,
+            sounding: [None; CHANNELS],
+            rng: Self::RNG_SEED,
+        }
+    }
+
+    #[inline]
+    pub fn pattern(&self) -> &Pattern<CHANNELS> {
+        &self.pattern
+    }
+
+    #[inline]
+    pub fn pattern_mut(&mut self) -> &mut Pattern<CHANNELS> {
+        &mut self.pattern
+    }
+
+    #[inline]
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Start the pattern from the first step at the clock tick
+    pub fn play(&mut self, clock: &Clock) {
+        self.playing = true;
+        self.next_step = 0;
+        self.grid_time = 0.0;
+        self.started_at = clock.tick;
+        self.rng = Self::RNG_SEED;
+    }
+
+    /// Stop the playback releasing sounding notes
+    pub fn stop(&mut self, clock: &Clock, rack: &mut ChannelRack<CHANNELS>) {
+        self.playing = false;
+
+        self.sounding
+            .iter_mut()
+            .enumerate()
+            .for_each(|(channel, sounding)| {
+                if let Some(sounding) = sounding.take() {
+                    rack.note_off_channel(clock, channel, sounding.note, UnitInterval::MIN);
+                }
+            });
+    }
+
+    /// Trigger notes of the steps starting at the clock tick and release finished ones. Must be called every tick before rendering the rack.
+    #[inline]
+    pub fn tick(&mut self, clock: &Clock, rack: &mut ChannelRack<CHANNELS>) {
+        if !self.playing {
+            return;
+        }
+
+        self.sounding
+            .iter_mut()
+            .enumerate()
+            .for_each(|(channel, sounding)| {
+                if let Some(SoundingNote {
+                    note,
+                    off: Some(off),
+                }) = *sounding
+                {
+                    if is_due(off, clock.tick) {
+                        rack.note_off_channel(clock, channel, note, UnitInterval::MIN);
+                        *sounding = None;
+                    }
+                }
+            });
+
+        let step_len = self.pattern.step_len(clock.sample_rate).max(1.0);
+        while is_due(
+            self.step_at(self.next_step, self.grid_time, step_len),
+            clock.tick,
+        ) {
+            self.trigger_step(clock, rack, step_len);
+        }
+    }
+
+    /// Tick of the next step or note release after the clock tick, `None` if not playing. Nothing changes until then, so a block up to it can be rendered without ticking the sequencer.
+    #[inline]
+    pub fn next_event(&self, clock: &Clock) -> Option<Tick> {
+        if !self.playing {
+            return None;
+        }
+
+        let step_len = self.pattern.step_len(clock.sample_rate).max(1.0);
+        let next_step = self.step_at(self.next_step, self.grid_time, step_len);
+
+        self.sounding
+            .iter()
+            .filter_map(|sounding| sounding.and_then(|sounding| sounding.off))
+            .chain([next_step])
+            .min_by_key(|at| at.wrapping_sub(clock.tick))
+    }
+
+    #[inline]
+    fn step_at(&self, step: u64, grid_time: f64, step_len: f64) -> Tick {
+        self.started_at
+            .wrapping_add((grid_time + self.pattern.swing_delay(step, step_len)).round() as Tick)
+    }
+
+    fn trigger_step(&mut self, clock: &Clock, rack: &mut ChannelRack<CHANNELS>, step_len: f64) {
+        let step = self.next_step;
+        let index = (step % self.pattern.steps as u64) as usize;
+
+        self.next_step += 1;
+        self.grid_time += step_len;
+        let next_step_at = self.step_at(self.next_step, self.grid_time, step_len);
+
+        (0..CHANNELS).for_each(|channel| {
+            let played = self
+                .pattern
+                .step(channel, index)
+                .copied()
+                .filter(|step| self.chance(step.probability));
+            let previous = self.sounding[channel].take();
+
+            let Some(played) = played else {
+                if let Some(previous) = previous {
+                    rack.note_off_channel(clock, channel, previous.note, UnitInterval::MIN);
+                }
+                return;
+            };
+
+            match previous {
+                // Slide into the same note just holds it
+                Some(previous) if previous.off.is_none() && previous.note == played.note => {}
+                // Slide overlaps the notes
+                Some(previous) if previous.off.is_none() => {
+                    rack.note_on_channel(clock, channel, played.note, played.velocity);
+                    rack.note_off_channel(clock, channel, previous.note, UnitInterval::MIN);
+                }
+                Some(previous) => {
+                    rack.note_off_channel(clock, channel, previous.note, UnitInterval::MIN);
+                    rack.note_on_channel(clock, channel, played.note, played.velocity);
+                }
+                None => rack.note_on_channel(clock, channel, played.note, played.velocity),
+            }
+
+            let gate_len = ((next_step_at.wrapping_sub(clock.tick)) as f32 * played.gate.inner())
+                .round()
+                .max(1.0) as Tick;
+
+            self.sounding[channel] = Some(SoundingNote {
+                note: played.note,
+                off: (!played.slide).then_some(clock.tick.wrapping_add(gate_len)),
+            });
+        });
+    }
+
+    #[inline]
+    fn chance(&mut self, probability: UnitInterval) -> bool {
+        if probability == UnitInterval::MAX {
+            return true;
+        }
+
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+
+        (self.rng as f32 / u32::MAX as f32) < probability.inner()
+    }
+}
+
+/// Whether the tick `at` is reached by `tick`, ticks wrap so anything up to half of their range behind counts as passed
+#[inline]
+fn is_due(at: Tick, tick: Tick) -> bool {
+    tick.wrapping_sub(at) <= Tick::MAX / 2
+}
+
+#[cfg(feature = "egui")]
+impl<const CHANNELS: usize> crate::param::ui::EguiComponent for Sequencer<CHANNELS> {
+    fn egui(&mut self, ui: &mut egui::Ui, _params: crate::param::ui::DefaultUiParams) {
+        let pattern = &mut self.pattern;
+
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.add(egui::Slider::new(&mut pattern.bpm, 20.0..=300.0).text("BPM"));
+                ui.add(pattern.swing.widget().text("Swing"));
+
+                let mut steps = pattern.steps;
+                ui.add(
+                    egui::DragValue::new(&mut steps)
+                        .clamp_range(1..=PATTERN_MAX_STEPS)
+                        .prefix("Steps "),
+                );
+                pattern.set_steps(steps);
+            });
+
+            egui::Grid::new("pattern").show(ui, |ui| {
+                pattern
+                    .grids
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(channel, grid)| {
+                        ui.label(format!("{channel}"));
+
+                        grid[..pattern.steps].iter_mut().for_each(|step| {
+                            let mut enabled = step.is_some();
+                            let response = ui.checkbox(&mut enabled, "");
+
+                            if response.changed() {
+                                *step = enabled.then_some(Step::new(Note::C4));
+                            }
+
+                            if let Some(step) = step {
+                                response.context_menu(|ui| {
+                                    egui::ComboBox::from_id_source("step_note")
+                                        .selected_text(format!("{:?}", step.note))
+                                        .show_ui(ui, |ui| {
+                                            Note::each().for_each(|note| {
+                                                ui.selectable_value(
+                                                    &mut step.note,
+                                                    note,
+                                                    format!("{note:?}"),
+                                                );
+                                            });
+                                        });
+                                    ui.add(step.velocity.widget().text("Velocity"));
+                                    ui.add(step.gate.widget().text("Gate"));
+                                    ui.add(step.probability.widget().text("Probability"));
+                                    ui.checkbox(&mut step.slide, "Slide");
+                                });
+                            }
+                        });
+
+                        ui.end_row();
+                    });
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Sequencer, Step};
+    use crate::{
+        daw::{
+            channel_rack::{ChannelRack, Instrument},
+            Daw,
+        },
+        midi::{event::MidiEventListener, note::Note},
+        osc::clock::{Clock, Tick},
+        param::f32::UnitInterval,
+        sample::Frame,
+    };
+    use alloc::boxed::Box;
+
+    fn probe_level(note: Note) -> f32 {
+        (note as u8 + 1) as f32 / 128.0
+    }
+
+    /// Outputs the level unique to the last held note
+    struct NoteProbe(Option<Note>);
+
+    impl MidiEventListener for NoteProbe {
+        fn note_on(&mut self, _clock: &Clock, note: Note, _velocity: UnitInterval) {
+            self.0 = Some(note);
+        }
+
+        fn note_off(&mut self, _clock: &Clock, note: Note, _velocity: UnitInterval) {
+            if self.0 == Some(note) {
+                self.0 = None;
+            }
+        }
+    }
+
+    impl Instrument for NoteProbe {
+        fn tick(&mut self, _clock: &Clock) -> Frame {
+            Frame::mono(self.0.map_or(0.0, probe_level))
+        }
+
+        fn name(&self) -> &str {
+            "Probe"
+        }
+
+        #[cfg(feature = "egui")]
+        fn egui(&mut self, _ui: &mut egui::Ui, _params: (Clock,)) {}
+    }
+
+    #[test]
+    fn step_timing() {
+        let mut daw = Daw::<1, 1, 0>::new(48_000);
+        daw.rack_mut()
+            .push_instrument(Box::new(NoteProbe(None)))
+            .unwrap();
+
+        // 6000 samples per step, odd steps are 3000 samples late. Gate is a half of the swung step.
+        let pattern = daw.sequencer_mut().pattern_mut();
+        pattern.swing = UnitInterval::MAX;
+        pattern.set_steps(4);
+        *pattern.step_mut(0, 0).unwrap() = Some(Step::new(Note::C4));
+        *pattern.step_mut(0, 1).unwrap() = Some(Step {
+            slide: true,
+            ..Step::new(Note::E4)
+        });
+        *pattern.step_mut(0, 2).unwrap() = Some(Step::new(Note::G4));
+        daw.play_pattern();
+
+        let mut buffer = [Frame::zero(); 30_000];
+        daw.process_buffer(&mut buffer);
+        let note_at =
+            |tick: usize| Note::each().find(|&note| Frame::mono(probe_level(note)) == buffer[tick]);
+
+        let notes = [
+            0, 4_499, 4_500, 8_999, 9_000, 11_999, 12_000, 16_499, 16_500, 24_000,
+        ]
+        .map(note_at);
+        assert_eq!(
+            notes,
+            [
+                Some(Note::C4),
+                Some(Note::C4),
+                None,
+                None,
+                Some(Note::E4),
+                Some(Note::E4),
+                // Slide holds the note until the next one starts
+                Some(Note::G4),
+                Some(Note::G4),
+                None,
+                // Loop
+                Some(Note::C4),
+            ]
+        );
+    }
+
+    #[test]
+    fn gate_across_tick_wrap() {
+        let mut daw = Daw::<1, 1, 0>::new(48_000);
+        daw.rack_mut()
+            .push_instrument(Box::new(NoteProbe(None)))
+            .unwrap();
+        *daw.sequencer_mut().pattern_mut().step_mut(0, 0).unwrap() = Some(Step::new(Note::C4));
+
+        // The gate of 3000 samples ends after the tick counter wraps
+        daw.clock.set(Tick::MAX - 99);
+        daw.play_pattern();
+
+        let mut buffer = [Frame::zero(); 6_000];
+        buffer
+            .iter_mut()
+            .for_each(|frame| *frame = daw.tick_internal());
+
+        let c4 = Frame::mono(probe_level(Note::C4));
+        assert_eq!(buffer[0], c4);
+        assert_eq!(buffer[2_999], c4);
+        assert_eq!(buffer[3_000], Frame::zero());
+        assert_eq!(buffer[5_999], Frame::zero());
+    }
+
+    #[test]
+    fn next_event_across_tick_wrap() {
+        let mut rack = ChannelRack::<1>::new();
+        rack.push_instrument(Box::new(NoteProbe(None))).unwrap();
+
+        let mut sequencer = Sequencer::<1>::new();
+        *sequencer.pattern_mut().step_mut(0, 0).unwrap() = Some(Step::new(Note::C4));
+
+        // The gate of 3000 samples ends after the tick counter wraps
+        let clock = Clock::zero(48_000).with_tick(Tick::MAX - 99);
+        sequencer.play(&clock);
+        sequencer.tick(&clock, &mut rack);
+        assert_eq!(sequencer.next_event(&clock), Some(2_900));
+
+        let clock = clock.with_tick(2_899);
+        sequencer.tick(&clock, &mut rack);
+        assert_eq!(sequencer.next_event(&clock), Some(2_900));
+
+        let clock = clock.with_tick(2_900);
+        sequencer.tick(&clock, &mut rack);
+        assert_eq!(sequencer.next_event(&clock), Some(5_900));
+    }
+}
@@ -0,0 +1,339 @@
+use super::Daw;
+use crate::{
+    export::{
+        wav::{WavError, WavWriter},
+        ByteSink,
+    },
+    midi::note::Note,
+    osc::clock::Tick,
+    param::f32::UnitInterval,
+    sample::{time::SampleCount, Frame},
+};
+use alloc::vec::Vec;
+#[allow(unused)]
+use num_traits::Float as _;
+
+/// Maximum count of frames processed by DAW at once during offline rendering. Blocks are shortened to land exactly on event ticks.
+pub const RENDER_BLOCK_SIZE: usize = 512;
+
+/// Absolute sample value below which output is considered silent (about -90dBFS)
+pub const SILENCE_THRESHOLD: f32 = 3.2e-5;
+
+/// Count of consecutive silent frames after the last event to consider the render complete
+pub const SILENCE_WINDOW: Tick = 2_048;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoteEventKind {
+    NoteOn,
+    NoteOff,
+}
+
+/// Note event scheduled at `tick` relative to the render start
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteEvent {
+    pub tick: Tick,
+    pub kind: NoteEventKind,
+    pub note: Note,
+    pub velocity: UnitInterval,
+    /// Rack channel playing the note, e.g. MIDI channel of a file. Without channel the note is played as live input.
+    pub channel: Option<u8>,
+}
+
+impl NoteEvent {
+    #[inline]
+    pub fn on(tick: Tick, note: Note, velocity: UnitInterval) -> Self {
+        Self {
+            tick,
+            kind: NoteEventKind::NoteOn,
+            note,
+            velocity,
+            channel: None,
+        }
+    }
+
+    #[inline]
+    pub fn off(tick: Tick, note: Note, velocity: UnitInterval) -> Self {
+        Self {
+            tick,
+            kind: NoteEventKind::NoteOff,
+            note,
+            velocity,
+            channel: None,
+        }
+    }
+
+    #[inline]
+    pub fn with_channel(self, channel: u8) -> Self {
+        Self {
+            channel: Some(channel),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderLength {
+    /// Render exactly given count of frames, events past the end are ignored
+    Fixed(SampleCount),
+    /// Render until the output stays silent for [`SILENCE_WINDOW`] frames after the last event, but no longer than `tail_limit` past the last event
+    UntilSilent { tail_limit: SampleCount },
+}
+
+/// Destination of rendered frames
+pub trait FrameSink {
+    type Error;
+
+    fn write_frames(&mut self, frames: &[Frame]) -> Result<(), Self::Error>;
+}
+
+impl FrameSink for Vec<Frame> {
+    type Error = core::convert::Infallible;
+
+    #[inline]
+    fn write_frames(&mut self, frames: &[Frame]) -> Result<(), Self::Error> {
+        self.extend_from_slice(frames);
+        Ok(())
+    }
+}
+
+impl<S: ByteSink> FrameSink for WavWriter<S> {
+    type Error = WavError<S::Error>;
+
+    #[inline]
+    fn write_frames(&mut self, frames: &[Frame]) -> Result<(), Self::Error> {
+        self.write_buffer(frames)
+    }
+}
+
+impl<S: FrameSink + ?Sized> FrameSink for &mut S {
+    type Error = S::Error;
+
+    #[inline]
+    fn write_frames(&mut self, frames: &[Frame]) -> Result<(), Self::Error> {
+        (**self).write_frames(frames)
+    }
+}
+
+impl<const CHANNEL_RACK_SIZE: usize, const MIXER_SIZE: usize, const FX_SLOTS: usize>
+    Daw<CHANNEL_RACK_SIZE, MIXER_SIZE, FX_SLOTS>
+{
+    #[inline]
+    fn dispatch_note_event(&mut self, event: &NoteEvent) {
+        match (event.kind, event.channel) {
+            (NoteEventKind::NoteOn, None) => self.note_on(event.note, event.velocity),
+            (NoteEventKind::NoteOff, None) => self.note_off(event.note, event.velocity),
+            (NoteEventKind::NoteOn, Some(channel)) => {
+                self.channel_note_on(channel as usize, event.note, event.velocity)
+            }
+            (NoteEventKind::NoteOff, Some(channel)) => {
+                self.channel_note_off(channel as usize, event.note, event.velocity)
+            }
+        }
+    }
+
+    /// Offline rendering. Drives DAW with [`Daw::process_buffer`] block by block, triggering `events` (which must be sorted by tick) on exact ticks, and writes the output to `sink`. Event ticks are relative to the current DAW clock.
+    ///
+    /// Returns the count of rendered frames.
+    pub fn render<S: FrameSink>(
+        &mut self,
+        events: &[NoteEvent],
+        length: RenderLength,
+        mut sink: S,
+    ) -> Result<Tick, S::Error> {
+        debug_assert!(
+            events.windows(2).all(|pair| pair[0].tick <= pair[1].tick),
+            "Render events must be sorted by tick"
+        );
+
+        let end = match length {
+            RenderLength::Fixed(length) => length.inner(),
+            RenderLength::UntilSilent { tail_limit } => events
+                .last()
+                .map_or(0, |event| event.tick)
+                .saturating_add(tail_limit.inner()),
+        };
+        let until_silent = matches!(length, RenderLength::UntilSilent { .. });
+
+        let mut buffer = [Frame::zero(); RENDER_BLOCK_SIZE];
+        let mut events = events.iter().peekable();
+        let mut rendered: Tick = 0;
+        let mut silent_for: Tick = 0;
+
+        loop {
+            while let Some(event) = events.next_if(|event| event.tick <= rendered) {
+                self.dispatch_note_event(event);
+            }
+
+            if rendered >= end || (until_silent && silent_for >= SILENCE_WINDOW) {
+                break;
+            }
+
+            let block_end = events.peek().map_or(end, |event| event.tick.min(end));
+            let block_len = ((block_end - rendered) as usize).min(RENDER_BLOCK_SIZE);
+            let block = &mut buffer[..block_len];
+
+            self.process_buffer(block);
+            sink.write_frames(block)?;
+            rendered += block_len as Tick;
+
+            if events.peek().is_some() {
+                silent_for = 0;
+            } else {
+                silent_for = block.iter().fold(silent_for, |silent_for, frame| {
+                    if frame
+                        .into_iter()
+                        .all(|sample| sample.abs() < SILENCE_THRESHOLD)
+                    {
+                        silent_for + 1
+                    } else {
+                        0
+                    }
+                });
+            }
+        }
+
+        Ok(rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NoteEvent, RenderLength, SILENCE_WINDOW};
+    use crate::{
+        daw::Daw,
+        export::wav::{WavFormat, WavSpec, WavWriter},
+        midi::note::Note,
+        param::f32::UnitInterval,
+        sample::{time::SampleCount, Frame},
+        wavetable::synth::create_basic_wavetable_synth,
+    };
+    use alloc::{boxed::Box, vec::Vec};
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn daw() -> Daw<1, 1, 0> {
+        let mut daw = Daw::new(SAMPLE_RATE);
+        let channel = daw
+            .rack_mut()
+            .push_instrument(Box::new(create_basic_wavetable_synth::<4, 0, 1, 1>(
+                SAMPLE_RATE,
+            )))
+            .unwrap();
+        daw.rack_mut().set_active(channel);
+        daw
+    }
+
+    fn events() -> [NoteEvent; 4] {
+        [
+            NoteEvent::on(100, Note::A4, UnitInterval::MAX),
+            NoteEvent::on(1_000, Note::C5, UnitInterval::MAX),
+            NoteEvent::off(1_777, Note::A4, UnitInterval::MAX),
+            NoteEvent::off(2_049, Note::C5, UnitInterval::MAX),
+        ]
+    }
+
+    #[test]
+    fn render_matches_ticking() {
+        let events = events();
+
+        let mut rendered = Vec::new();
+        let len = daw()
+            .render(
+                &events,
+                RenderLength::Fixed(SampleCount::new(3_000)),
+                &mut rendered,
+            )
+            .unwrap();
+        assert_eq!(len, 3_000);
+        assert_eq!(rendered.len(), 3_000);
+
+        let mut daw = daw();
+        let mut events = events.iter().peekable();
+        let ticked = (0..3_000)
+            .map(|tick| {
+                while let Some(event) = events.next_if(|event| event.tick == tick) {
+                    daw.dispatch_note_event(event);
+                }
+                daw.tick_internal()
+            })
+            .collect::<Vec<Frame>>();
+
+        assert_eq!(rendered, ticked);
+    }
+
+    #[test]
+    fn render_channels() {
+        let mut daw = Daw::<2, 1, 0>::new(SAMPLE_RATE);
+        (0..2).for_each(|_| {
+            daw.rack_mut()
+                .push_instrument(Box::new(create_basic_wavetable_synth::<4, 0, 1, 1>(
+                    SAMPLE_RATE,
+                )))
+                .unwrap();
+        });
+
+        // No channel is active, the addressed one plays anyway
+        let events = events().map(|event| event.with_channel(1));
+        let mut rendered = Vec::new();
+        daw.render(
+            &events,
+            RenderLength::Fixed(SampleCount::new(2_000)),
+            &mut rendered,
+        )
+        .unwrap();
+
+        assert!(rendered[..100].iter().all(|frame| *frame == Frame::zero()));
+        assert!(rendered[100..].iter().any(|frame| *frame != Frame::zero()));
+    }
+
+    #[test]
+    fn render_until_silent() {
+        let events = events();
+
+        // Empty DAW is silent right after the last event
+        let len = Daw::<1, 1, 0>::new(SAMPLE_RATE)
+            .render(
+                &events,
+                RenderLength::UntilSilent {
+                    tail_limit: SampleCount::from_secs(1, SAMPLE_RATE),
+                },
+                Vec::new(),
+            )
+            .unwrap();
+
+        assert_eq!(len, events[3].tick + SILENCE_WINDOW);
+    }
+
+    #[test]
+    fn render_until_silent_tail_limit() {
+        // Held note never becomes silent
+        let events = &events()[..3];
+        let tail_limit = SampleCount::from_millis(100, SAMPLE_RATE);
+
+        let len = daw()
+            .render(events, RenderLength::UntilSilent { tail_limit }, Vec::new())
+            .unwrap();
+
+        assert_eq!(len, events[2].tick + tail_limit.inner());
+    }
+
+    #[test]
+    fn render_to_wav() {
+        let spec = WavSpec::new(SAMPLE_RATE, WavFormat::Pcm16);
+        let mut writer = WavWriter::new(Vec::new(), spec).unwrap();
+
+        let len = daw()
+            .render(
+                &events(),
+                RenderLength::Fixed(SampleCount::new(1_234)),
+                &mut writer,
+            )
+            .unwrap();
+
+        assert_eq!(writer.frames_written(), len);
+        assert_eq!(
+            writer.finalize().unwrap().len(),
+            spec.header_len() + len as usize * WavFormat::Pcm16.bytes_per_frame()
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use alloc::vec::Vec;
+
+pub mod wav;
+
+/// Destination for encoded bytes. Exporters only ever append to the sink, so it can be a file, a serial port, a flash page writer, etc.
+pub trait ByteSink {
+    type Error;
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Sink supporting overwriting of already written bytes. Streaming exporters need it to patch headers (e.g. chunk sizes) once the length of the data is known.
+pub trait SeekableByteSink: ByteSink {
+    /// Overwrite bytes at `offset` from the start of the sink. The range must already be written.
+    fn overwrite(&mut self, offset: usize, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl<S: ByteSink + ?Sized> ByteSink for &mut S {
+    type Error = S::Error;
+
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        (**self).write_bytes(bytes)
+    }
+}
+
+impl<S: SeekableByteSink + ?Sized> SeekableByteSink for &mut S {
+    #[inline]
+    fn overwrite(&mut self, offset: usize, bytes: &[u8]) -> Result<(), Self::Error> {
+        (**self).overwrite(offset, bytes)
+    }
+}
+
+impl ByteSink for Vec<u8> {
+    type Error = core::convert::Infallible;
+
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl SeekableByteSink for Vec<u8> {
+    #[inline]
+    fn overwrite(&mut self, offset: usize, bytes: &[u8]) -> Result<(), Self::Error> {
+        self[offset..offset + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// The slice sink is out of space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SliceOverflow;
+
+/// Fixed-size sink over a byte slice, for targets without allocator
+pub struct SliceSink<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> SliceSink<'a> {
+    #[inline]
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    /// Count of bytes written
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pos
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pos == 0
+    }
+
+    /// Written part of the underlying slice
+    #[inline]
+    pub fn written(&self) -> &[u8] {
+        &self.buf[..self.pos]
+    }
+}
+
+impl ByteSink for SliceSink<'_> {
+    type Error = SliceOverflow;
+
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        let end = self.pos + bytes.len();
+
+        self.buf
+            .get_mut(self.pos..end)
+            .ok_or(SliceOverflow)?
+            .copy_from_slice(bytes);
+        self.pos = end;
+
+        Ok(())
+    }
+}
+
+impl SeekableByteSink for SliceSink<'_> {
+    #[inline]
+    fn overwrite(&mut self, offset: usize, bytes: &[u8]) -> Result<(), Self::Error> {
+        let end = offset + bytes.len();
+
+        if end > self.pos {
+            return Err(SliceOverflow);
+        }
+
+        self.buf[offset..end].copy_from_slice(bytes);
+
+        Ok(())
+    }
+}
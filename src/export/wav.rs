@@ -1 +1,414 @@
-// TODO
+use super::{ByteSink, SeekableByteSink};
+use crate::sample::Frame;
+#[allow(unused)]
+use num_traits::Float as _;
+
+/// Exported frames are always stereo
+pub const CHANNELS: u16 = 2;
+
+pub(crate) const WAVE_FORMAT_PCM: u16 = 1;
+pub(crate) const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+
+/// The largest header written: RIFF + fmt (18 bytes body) + fact + data chunk header
+const MAX_HEADER_LEN: usize = 12 + 8 + 18 + 12 + 8;
+
+/// Sample encoding of the exported file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavFormat {
+    Pcm16,
+    Pcm24,
+    Float32,
+}
+
+impl WavFormat {
+    #[inline]
+    pub const fn bytes_per_sample(self) -> usize {
+        match self {
+            WavFormat::Pcm16 => 2,
+            WavFormat::Pcm24 => 3,
+            WavFormat::Float32 => 4,
+        }
+    }
+
+    #[inline]
+    pub const fn bytes_per_frame(self) -> usize {
+        self.bytes_per_sample() * CHANNELS as usize
+    }
+
+    /// Format of `fmt ` chunk format tag and bit depth, `None` for unsupported encodings
+    #[inline]
+    pub(crate) const fn from_tag(format_tag: u16, bits_per_sample: u16) -> Option<Self> {
+        match (format_tag, bits_per_sample) {
+            (WAVE_FORMAT_PCM, 16) => Some(WavFormat::Pcm16),
+            (WAVE_FORMAT_PCM, 24) => Some(WavFormat::Pcm24),
+            (WAVE_FORMAT_IEEE_FLOAT, 32) => Some(WavFormat::Float32),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn format_tag(self) -> u16 {
+        match self {
+            WavFormat::Pcm16 | WavFormat::Pcm24 => WAVE_FORMAT_PCM,
+            WavFormat::Float32 => WAVE_FORMAT_IEEE_FLOAT,
+        }
+    }
+
+    /// Non-PCM formats require the extended `fmt ` chunk and the `fact` chunk
+    #[inline]
+    const fn is_pcm(self) -> bool {
+        matches!(self.format_tag(), WAVE_FORMAT_PCM)
+    }
+
+    /// Encode a single sample into little-endian bytes returning the count of bytes written. PCM samples are clipped to [-1.0; 1.0], float samples are written as is.
+    #[inline]
+    fn encode(self, sample: f32, out: &mut [u8]) -> usize {
+        match self {
+            WavFormat::Pcm16 => {
+                let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+                out[..2].copy_from_slice(&value.to_le_bytes());
+            }
+            WavFormat::Pcm24 => {
+                const MAX_24: f32 = ((1 << 23) - 1) as f32;
+                let value = (sample.clamp(-1.0, 1.0) * MAX_24).round() as i32;
+                out[..3].copy_from_slice(&value.to_le_bytes()[..3]);
+            }
+            WavFormat::Float32 => {
+                out[..4].copy_from_slice(&sample.to_le_bytes());
+            }
+        }
+
+        self.bytes_per_sample()
+    }
+
+    /// Decode a single little-endian sample of [`WavFormat::bytes_per_sample`] bytes. PCM samples are scaled to [-1.0; 1.0].
+    #[inline]
+    pub(crate) fn decode(self, bytes: &[u8]) -> f32 {
+        match self {
+            WavFormat::Pcm16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32,
+            WavFormat::Pcm24 => {
+                const MAX_24: f32 = ((1 << 23) - 1) as f32;
+                // Sign is extended by the arithmetic shift
+                let value = i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8;
+                value as f32 / MAX_24
+            }
+            WavFormat::Float32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WavSpec {
+    pub sample_rate: u32,
+    pub format: WavFormat,
+}
+
+impl WavSpec {
+    #[inline]
+    pub const fn new(sample_rate: u32, format: WavFormat) -> Self {
+        Self {
+            sample_rate,
+            format,
+        }
+    }
+
+    #[inline]
+    pub const fn header_len(&self) -> usize {
+        if self.format.is_pcm() {
+            12 + 8 + 16 + 8
+        } else {
+            MAX_HEADER_LEN
+        }
+    }
+
+    /// Offset of the `fact` chunk sample count, only present for non-PCM formats
+    #[inline]
+    const fn fact_offset(&self) -> Option<usize> {
+        if self.format.is_pcm() {
+            None
+        } else {
+            Some(12 + 8 + 18 + 8)
+        }
+    }
+
+    /// Size of `data` chunk for given count of frames, `None` if it does not fit into RIFF
+    #[inline]
+    fn data_len(&self, frames: u32) -> Option<u32> {
+        let data_len = (frames as u64) * self.format.bytes_per_frame() as u64;
+        let riff_len = data_len + self.header_len() as u64 - 8;
+
+        if riff_len > u32::MAX as u64 {
+            None
+        } else {
+            Some(data_len as u32)
+        }
+    }
+
+    /// Value of the RIFF chunk size field for given `data` chunk size
+    #[inline]
+    fn riff_len(&self, data_len: u32) -> u32 {
+        data_len + self.header_len() as u32 - 8
+    }
+
+    fn header(&self, frames: u32, data_len: u32) -> ([u8; MAX_HEADER_LEN], usize) {
+        let mut header = [0; MAX_HEADER_LEN];
+        let mut pos = 0;
+        let mut put = |bytes: &[u8]| {
+            header[pos..pos + bytes.len()].copy_from_slice(bytes);
+            pos += bytes.len();
+        };
+
+        let block_align = self.format.bytes_per_frame() as u16;
+        let bits_per_sample = self.format.bytes_per_sample() as u16 * 8;
+
+        put(b"RIFF");
+        put(&self.riff_len(data_len).to_le_bytes());
+        put(b"WAVE");
+
+        put(b"fmt ");
+        put(&(if self.format.is_pcm() { 16u32 } else { 18 }).to_le_bytes());
+        put(&self.format.format_tag().to_le_bytes());
+        put(&CHANNELS.to_le_bytes());
+        put(&self.sample_rate.to_le_bytes());
+        put(&(self.sample_rate * block_align as u32).to_le_bytes());
+        put(&block_align.to_le_bytes());
+        put(&bits_per_sample.to_le_bytes());
+
+        if !self.format.is_pcm() {
+            // cbSize
+            put(&0u16.to_le_bytes());
+
+            put(b"fact");
+            put(&4u32.to_le_bytes());
+            put(&frames.to_le_bytes());
+        }
+
+        put(b"data");
+        put(&data_len.to_le_bytes());
+
+        debug_assert_eq!(pos, self.header_len());
+
+        (header, pos)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavError<E> {
+    /// The sink failed to accept bytes
+    Sink(E),
+    /// Data does not fit into 4GiB RIFF limit
+    TooLong,
+    /// Count of written frames differs from the one declared in the header
+    LengthMismatch { declared: u32, written: u32 },
+}
+
+impl<E> From<E> for WavError<E> {
+    #[inline]
+    fn from(value: E) -> Self {
+        Self::Sink(value)
+    }
+}
+
+/// WAV encoder writing stereo frames into a [`ByteSink`].
+///
+/// There are two modes:
+/// - Streaming ([`WavWriter::new`]), where the length is not known upfront. Chunk sizes are patched by [`WavWriter::finalize`], thus the sink must be seekable.
+/// - Sized ([`WavWriter::with_len`]), where the header is final from the start, so any append-only sink works.
+pub struct WavWriter<S: ByteSink> {
+    sink: S,
+    spec: WavSpec,
+    frames: u32,
+    /// Count of frames written into the header, zero for streaming mode
+    declared: u32,
+}
+
+impl<S: ByteSink> WavWriter<S> {
+    /// Start streaming export of unknown length
+    pub fn new(mut sink: S, spec: WavSpec) -> Result<Self, WavError<S::Error>> {
+        let (header, len) = spec.header(0, 0);
+        sink.write_bytes(&header[..len])?;
+
+        Ok(Self {
+            sink,
+            spec,
+            frames: 0,
+            declared: 0,
+        })
+    }
+
+    /// Start export of exactly `frames` frames
+    pub fn with_len(mut sink: S, spec: WavSpec, frames: u32) -> Result<Self, WavError<S::Error>> {
+        let data_len = spec.data_len(frames).ok_or(WavError::TooLong)?;
+        let (header, len) = spec.header(frames, data_len);
+        sink.write_bytes(&header[..len])?;
+
+        Ok(Self {
+            sink,
+            spec,
+            frames: 0,
+            declared: frames,
+        })
+    }
+
+    #[inline]
+    pub fn spec(&self) -> WavSpec {
+        self.spec
+    }
+
+    #[inline]
+    pub fn frames_written(&self) -> u32 {
+        self.frames
+    }
+
+    #[inline]
+    pub fn write_frame(&mut self, frame: Frame) -> Result<(), WavError<S::Error>> {
+        let frames = self.frames.checked_add(1).ok_or(WavError::TooLong)?;
+        self.spec.data_len(frames).ok_or(WavError::TooLong)?;
+
+        let mut bytes = [0; 8];
+        let len = frame.into_iter().fold(0, |len, sample| {
+            len + self.spec.format.encode(sample, &mut bytes[len..])
+        });
+
+        self.sink.write_bytes(&bytes[..len])?;
+        self.frames = frames;
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn write_buffer(&mut self, buffer: &[Frame]) -> Result<(), WavError<S::Error>> {
+        buffer.iter().try_for_each(|frame| self.write_frame(*frame))
+    }
+
+    /// Finish sized export, returning the sink. Streaming export must be finished with [`WavWriter::finalize`] instead.
+    pub fn finish(self) -> Result<S, WavError<S::Error>> {
+        if self.declared == self.frames {
+            Ok(self.sink)
+        } else {
+            Err(WavError::LengthMismatch {
+                declared: self.declared,
+                written: self.frames,
+            })
+        }
+    }
+}
+
+impl<S: SeekableByteSink> WavWriter<S> {
+    /// Patch RIFF, `data` (and `fact`) chunk sizes with actual count of written frames and return the sink. Works for both streaming and sized modes, in sized mode the header is just rewritten.
+    pub fn finalize(mut self) -> Result<S, WavError<S::Error>> {
+        let data_len = self.spec.data_len(self.frames).ok_or(WavError::TooLong)?;
+
+        self.sink
+            .overwrite(4, &self.spec.riff_len(data_len).to_le_bytes())?;
+
+        if let Some(fact_offset) = self.spec.fact_offset() {
+            self.sink
+                .overwrite(fact_offset, &self.frames.to_le_bytes())?;
+        }
+
+        self.sink
+            .overwrite(self.spec.header_len() - 4, &data_len.to_le_bytes())?;
+
+        Ok(self.sink)
+    }
+}
+
+/// Encode whole buffer at once
+pub fn write_wav<S: ByteSink>(
+    sink: S,
+    spec: WavSpec,
+    buffer: &[Frame],
+) -> Result<S, WavError<S::Error>> {
+    let frames = u32::try_from(buffer.len()).map_err(|_| WavError::TooLong)?;
+    let mut writer = WavWriter::with_len(sink, spec, frames)?;
+    writer.write_buffer(buffer)?;
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_wav, WavFormat, WavSpec, WavWriter};
+    use crate::{
+        export::{SliceOverflow, SliceSink},
+        sample::Frame,
+    };
+    use alloc::vec::Vec;
+
+    const FORMATS: [WavFormat; 3] = [WavFormat::Pcm16, WavFormat::Pcm24, WavFormat::Float32];
+
+    fn frames() -> Vec<Frame> {
+        (0..100)
+            .map(|index| Frame::stereo(index as f32 / 100.0, -(index as f32) / 100.0))
+            .collect()
+    }
+
+    #[test]
+    fn pcm16_header() {
+        let bytes = write_wav(
+            Vec::new(),
+            WavSpec::new(48_000, WavFormat::Pcm16),
+            &[Frame::stereo(1.0, -1.0)],
+        )
+        .unwrap();
+
+        assert_eq!(bytes.len(), 44 + 4);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()), 40);
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        // Channels
+        assert_eq!(u16::from_le_bytes(bytes[22..24].try_into().unwrap()), 2);
+        // Sample rate
+        assert_eq!(
+            u32::from_le_bytes(bytes[24..28].try_into().unwrap()),
+            48_000
+        );
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), 4);
+        assert_eq!(
+            i16::from_le_bytes(bytes[44..46].try_into().unwrap()),
+            i16::MAX
+        );
+        assert_eq!(
+            i16::from_le_bytes(bytes[46..48].try_into().unwrap()),
+            -i16::MAX
+        );
+    }
+
+    #[test]
+    fn streaming_equals_sized() {
+        let frames = frames();
+
+        for format in FORMATS {
+            let spec = WavSpec::new(44_100, format);
+
+            let sized = write_wav(Vec::new(), spec, &frames).unwrap();
+
+            let mut writer = WavWriter::new(Vec::new(), spec).unwrap();
+            frames
+                .chunks(7)
+                .for_each(|chunk| writer.write_buffer(chunk).unwrap());
+            let streamed = writer.finalize().unwrap();
+
+            assert_eq!(sized, streamed, "{format:?}");
+            assert_eq!(
+                sized.len(),
+                spec.header_len() + frames.len() * format.bytes_per_frame()
+            );
+        }
+    }
+
+    #[test]
+    fn slice_sink_overflow() {
+        let mut buf = [0; 50];
+        let spec = WavSpec::new(48_000, WavFormat::Pcm16);
+
+        let mut writer = WavWriter::new(SliceSink::new(&mut buf), spec).unwrap();
+        writer.write_frame(Frame::zero()).unwrap();
+
+        assert!(matches!(
+            writer.write_frame(Frame::zero()),
+            Err(super::WavError::Sink(SliceOverflow))
+        ));
+    }
+}
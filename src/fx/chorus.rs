@@ -1,22 +1,254 @@
-use crate::sample::Frame;
+use super::Fx;
+use crate::{
+    buffer::DelayLine,
+    midi::event::MidiEventListener,
+    modx::lfo::LfoWaveform,
+    osc::clock::{Clock, Freq},
+    param::f32::UnitInterval,
+    sample::{time::SampleCount, Frame},
+    voice::controller::voices_stereo_spread,
+};
+#[allow(unused)]
+use num_traits::Float as _;
 
+pub const CHORUS_MAX_VOICES: u8 = 8;
+
+#[derive(Debug, Clone, Copy)]
 pub struct ChorusParams {
-    voices: u8,
+    pub voices: u8,
+    /// Voices LFO rate
+    pub rate: Freq,
+    pub waveform: LfoWaveform,
+    /// Shortest voice delay
+    pub delay: SampleCount,
+    /// Voice delay swings in range [delay; delay + depth]
+    pub depth: SampleCount,
+    /// Voices stereo spread
+    pub spread: UnitInterval,
+    /// Dry/wet
+    pub mix: UnitInterval,
+}
+
+impl ChorusParams {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            voices: 3,
+            rate: Freq::mHz(800),
+            waveform: LfoWaveform::Sine,
+            delay: SampleCount::from_millis(8, sample_rate),
+            depth: SampleCount::from_millis(4, sample_rate),
+            spread: UnitInterval::MAX,
+            mix: UnitInterval::EQUILIBRIUM,
+        }
+    }
+}
+
+/// Multi-voice modulated delay chorus. `SIZE` is the delay line length, must be larger than the longest `delay + depth`.
+pub struct Chorus<const SIZE: usize> {
+    line: DelayLine<SIZE>,
+    /// Phase of the first voice LFO, other voices are evenly offset from it
+    phase: f32,
 }
 
-pub struct Chorus {
-    index: usize,
+impl<const SIZE: usize> Default for Chorus<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl Chorus {
+impl<const SIZE: usize> Chorus<SIZE> {
     pub fn new() -> Self {
-        Self { index: 0 }
+        Self {
+            line: DelayLine::new(),
+            phase: 0.0,
+        }
+    }
+
+    pub fn tick(&mut self, clock: &Clock, input: Frame, params: &ChorusParams) -> Frame {
+        self.line.push(input.mono_sum() * 0.5);
+
+        let voices = params.voices.clamp(1, CHORUS_MAX_VOICES) as usize;
+
+        let wet = voices_stereo_spread(voices, params.spread)
+            .enumerate()
+            .fold(Frame::zero(), |wet, (voice, balance)| {
+                let phase = (self.phase + voice as f32 / voices as f32).fract();
+                let swing = (params.waveform.at(phase) + 1.0) * 0.5;
+                let delay = params.delay.inner() as f32 + params.depth.inner() as f32 * swing;
+
+                let sample = self.line.read(delay);
+
+                // Constant-power panning, boosted back to unity at center
+                wet + Frame::stereo(
+                    sample * (2.0 * balance.inner()).sqrt(),
+                    sample * (2.0 * (1.0 - balance.inner())).sqrt(),
+                )
+            })
+            / Frame::equal(voices as f32);
+
+        self.phase = (self.phase + params.rate.inner() / clock.sample_rate as f32).fract();
+
+        input * (1.0 - params.mix.inner()) + wet * params.mix.inner()
+    }
+}
+
+/// [`Chorus`] effect owning its parameters
+pub struct ChorusFx<const SIZE: usize> {
+    chorus: Chorus<SIZE>,
+    params: ChorusParams,
+}
+
+impl<const SIZE: usize> ChorusFx<SIZE> {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            chorus: Chorus::new(),
+            params: ChorusParams::new(sample_rate),
+        }
+    }
+
+    #[inline]
+    pub fn params_mut(&mut self) -> &mut ChorusParams {
+        &mut self.params
+    }
+}
+
+impl<const SIZE: usize> MidiEventListener for ChorusFx<SIZE> {
+    #[inline]
+    fn note_on(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
+        let _ = clock;
+        let _ = note;
+        let _ = velocity;
+    }
+
+    #[inline]
+    fn note_off(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
+        let _ = clock;
+        let _ = note;
+        let _ = velocity;
+    }
+}
+
+impl<const SIZE: usize> Fx for ChorusFx<SIZE> {
+    #[inline]
+    fn tick(&mut self, clock: &Clock, input: Frame) -> Frame {
+        self.chorus.tick(clock, input, &self.params)
+    }
+
+    #[inline]
+    fn name(&self) -> &str {
+        "Chorus"
     }
 
-    pub fn tick(&mut self, input: Frame, params: &ChorusParams) -> Frame {
-        // for voice in 0..params.voices {
+    #[cfg(feature = "egui")]
+    fn egui(&mut self, ui: &mut egui::Ui, (clock,): (Clock,)) {
+        ui.vertical(|ui| {
+            ui.label(self.name());
+
+            let params = &mut self.params;
+
+            ui.add(
+                egui::Slider::from_get_set(1.0..=CHORUS_MAX_VOICES as f64, |new_value| {
+                    if let Some(new_value) = new_value {
+                        params.voices = new_value as u8;
+                    }
+
+                    params.voices as f64
+                })
+                .integer()
+                .text("Voices"),
+            );
+
+            ui.add(
+                params
+                    .rate
+                    .widget(Some(Freq::mHz(10)..=Freq::Hz(10)))
+                    .logarithmic(true)
+                    .text("Rate"),
+            );
+
+            let max_time = SampleCount::new((SIZE / 2) as u32);
+            ui.add(
+                params
+                    .delay
+                    .widget(clock, Some((SampleCount::new(1), max_time)))
+                    .text("Delay"),
+            );
+            ui.add(
+                params
+                    .depth
+                    .widget(clock, Some((SampleCount::zero(), max_time)))
+                    .text("Depth"),
+            );
+
+            ui.add(params.spread.widget().text("Spread"));
+            ui.add(params.mix.widget().text("Dry/wet"));
+
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut params.waveform, LfoWaveform::Sine, "Sine");
+                ui.radio_value(&mut params.waveform, LfoWaveform::Triangle, "Triangle");
+                ui.radio_value(&mut params.waveform, LfoWaveform::Saw, "Saw");
+            });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Chorus, ChorusParams, CHORUS_MAX_VOICES};
+    use crate::{
+        osc::clock::{Clock, Freq},
+        param::f32::UnitInterval,
+        sample::{time::SampleCount, Frame},
+    };
+    use core::f32::consts::{SQRT_2, TAU};
+    #[allow(unused)]
+    use num_traits::Float as _;
+
+    #[test]
+    fn modulated_delay() {
+        const DELAY: u32 = 10;
+
+        let clock = Clock::zero(48_000);
+        let input = |tick: u32| Frame::mono((TAU * tick as f32 / 37.0).sin());
+
+        // Without depth a single voice is the input delayed by the base delay
+        let mut chorus = Chorus::<64>::new();
+        let params = ChorusParams {
+            voices: 1,
+            delay: SampleCount::new(DELAY),
+            depth: SampleCount::zero(),
+            mix: UnitInterval::EQUILIBRIUM,
+            ..ChorusParams::new(clock.sample_rate)
+        };
+        clock.for_buffer(200).for_each(|clock| {
+            let output = chorus.tick(&clock, input(clock.tick), &params);
+            let delayed = clock.tick.checked_sub(DELAY).map_or(Frame::zero(), input);
+            let expected = (input(clock.tick) + delayed) * Frame::equal(0.5);
+
+            output
+                .into_iter()
+                .zip(expected)
+                .for_each(|(output, expected)| {
+                    assert!((output - expected).abs() < 1e-5, "{}", clock.tick);
+                });
+        });
 
-        // }
-        todo!()
+        // Full depth and rate with all voices spread stays within constant-power panning gain
+        let mut chorus = Chorus::<1024>::new();
+        let params = ChorusParams {
+            voices: CHORUS_MAX_VOICES,
+            rate: Freq::Hz(10),
+            depth: SampleCount::new(500),
+            spread: UnitInterval::MAX,
+            mix: UnitInterval::MAX,
+            ..ChorusParams::new(clock.sample_rate)
+        };
+        clock.for_buffer(10_000).for_each(|clock| {
+            let output = chorus.tick(&clock, input(clock.tick), &params);
+            assert!(
+                output.into_iter().all(|sample| sample.abs() <= SQRT_2),
+                "{output:?}"
+            );
+        });
     }
 }
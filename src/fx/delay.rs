@@ -1,69 +1,300 @@
-use super::filter::one_pole::OnePole;
+use super::{
+    filter::svf::{Svf, SvfMode},
+    Fx,
+};
 use crate::{
+    buffer::DelayLine,
+    midi::event::MidiEventListener,
     osc::clock::Clock,
     param::f32::UnitInterval,
-    sample::{time::SampleCount, Frame},
+    sample::{
+        time::{NoteDivision, SampleCount},
+        Frame,
+    },
 };
+#[allow(unused)]
+use num_traits::Float as _;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DelayKind {
     PingPong,
     Stereo,
 }
 
+/// Delay time either in samples or in note divisions relative to tempo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DelayTime {
+    Free(SampleCount),
+    Synced { division: NoteDivision, bpm: f32 },
+}
+
+impl DelayTime {
+    #[inline]
+    pub fn samples(&self, sample_rate: u32) -> f32 {
+        match self {
+            DelayTime::Free(time) => time.inner() as f32,
+            DelayTime::Synced { division, bpm } => division.samples(*bpm, sample_rate),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct DelayParams {
-    // TODO: Dry/wet instead of amount
-    pub amount: UnitInterval,
+    /// Dry/wet
+    pub mix: UnitInterval,
     pub feedback: UnitInterval,
-    pub time: SampleCount,
+    pub time: DelayTime,
     pub kind: DelayKind,
-    // TODO: Tone
+    /// Low-pass on the feedback path, from dark ([`DELAY_TONE_MIN_CUTOFF`]) to transparent ([`DELAY_TONE_MAX_CUTOFF`])
+    pub tone: UnitInterval,
+}
+
+impl DelayParams {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            mix: UnitInterval::EQUILIBRIUM,
+            feedback: UnitInterval::EQUILIBRIUM,
+            time: DelayTime::Free(SampleCount::from_millis(250, sample_rate)),
+            kind: DelayKind::Stereo,
+            tone: UnitInterval::new(0.6),
+        }
+    }
+
+    /// Feedback low-pass cutoff frequency, exponential over tone
+    #[inline]
+    pub fn tone_cutoff(&self) -> f32 {
+        DELAY_TONE_MIN_CUTOFF
+            * (DELAY_TONE_MAX_CUTOFF / DELAY_TONE_MIN_CUTOFF).powf(self.tone.inner())
+    }
 }
 
+pub const DELAY_TONE_MIN_CUTOFF: f32 = 200.0;
+pub const DELAY_TONE_MAX_CUTOFF: f32 = 20_000.0;
+
+/// Per-sample smoothing factor of delay time changes. Time glides instead of jumping so modulating it does not produce zipper noise.
+const TIME_SMOOTHING: f32 = 0.0005;
+
 pub struct Delay<const SIZE: usize> {
-    bb: Frame<[f32; SIZE]>,
-    flt: Frame<OnePole>,
+    /// Left and right lines, not a [`Frame`] as lines are not `Copy`
+    lines: [DelayLine<SIZE>; 2],
+    flt: Frame<Svf>,
+    /// Smoothed delay time in samples, `None` until the first tick
+    time: Option<f32>,
 }
 
 impl<const SIZE: usize> Delay<SIZE> {
-    pub fn new(sample_rate: u32) -> Self {
+    pub fn new() -> Self {
         Self {
-            bb: Frame::from_fn(|_| [0.0; SIZE]),
-            flt: Frame::from_fn(|_| {
-                let mut flt = OnePole::new();
-                flt.set_cutoff(2_000.0, sample_rate);
-                flt
-            }),
+            lines: [DelayLine::new(), DelayLine::new()],
+            flt: Frame::from_fn(|_| Svf::new()),
+            time: None,
         }
     }
 
     pub fn tick(&mut self, clock: &Clock, input: Frame, params: &DelayParams) -> Frame {
-        let time = (params.time.inner() as usize).clamp(0, SIZE);
-
-        let index = clock.tick as usize % time;
+        let target_time = params
+            .time
+            .samples(clock.sample_rate)
+            .clamp(1.0, DelayLine::<SIZE>::MAX_DELAY as f32 + 1.0);
 
-        // TODO: Lerp index
+        let time = self.time.map_or(target_time, |time| {
+            time + (target_time - time) * TIME_SMOOTHING
+        });
+        self.time = Some(time);
 
-        // Read feedback
-        let feedback = self.bb.at(index);
+        // Read delayed output, the line is not yet pushed with current input so the delay is one sample shorter
+        let [left, right] = &mut self.lines;
+        let delayed = Frame::stereo(left.read(time - 1.0), right.read(time - 1.0));
 
-        // Write new feedback
-        let write = input
-            .zip(feedback, |input, feedback| {
-                (input + feedback) * params.feedback.inner()
-            })
-            .zip_mut(&mut self.flt, |feedback, flt| flt.process(*feedback));
+        // Write input with filtered feedback
+        let tone_cutoff = params.tone_cutoff();
+        let feedback =
+            (delayed * params.feedback.inner()).zip_mut(&mut self.flt, |feedback, flt| {
+                flt.set_params(tone_cutoff, UnitInterval::MIN, clock.sample_rate);
+                flt.process_mode(*feedback, SvfMode::LowPass)
+            });
+        let write = input + feedback;
 
         let write = match params.kind {
             DelayKind::PingPong => write.swapped(),
             DelayKind::Stereo => write,
         };
 
-        self.bb.set(index, write);
+        left.push(*write.left());
+        right.push(*write.right());
+
+        input * (1.0 - params.mix.inner()) + delayed * params.mix.inner()
+    }
+}
+
+impl<const SIZE: usize> Default for Delay<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// [`Delay`] effect owning its parameters
+pub struct DelayFx<const SIZE: usize> {
+    delay: Delay<SIZE>,
+    params: DelayParams,
+}
 
-        let mix = input + feedback.map(|val| val * params.amount.inner());
+impl<const SIZE: usize> DelayFx<SIZE> {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            delay: Delay::new(),
+            params: DelayParams::new(sample_rate),
+        }
+    }
+
+    #[inline]
+    pub fn params_mut(&mut self) -> &mut DelayParams {
+        &mut self.params
+    }
+}
+
+impl<const SIZE: usize> MidiEventListener for DelayFx<SIZE> {
+    #[inline]
+    fn note_on(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
+        let _ = clock;
+        let _ = note;
+        let _ = velocity;
+    }
+
+    #[inline]
+    fn note_off(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
+        let _ = clock;
+        let _ = note;
+        let _ = velocity;
+    }
+}
+
+impl<const SIZE: usize> Fx for DelayFx<SIZE> {
+    #[inline]
+    fn tick(&mut self, clock: &Clock, input: Frame) -> Frame {
+        self.delay.tick(clock, input, &self.params)
+    }
+
+    #[inline]
+    fn name(&self) -> &str {
+        "Delay"
+    }
+
+    #[cfg(feature = "egui")]
+    fn egui(&mut self, ui: &mut egui::Ui, (clock,): (Clock,)) {
+        ui.vertical(|ui| {
+            ui.label(self.name());
+
+            let params = &mut self.params;
+
+            let mut synced = matches!(params.time, DelayTime::Synced { .. });
+            if ui.checkbox(&mut synced, "Tempo sync").changed() {
+                params.time = if synced {
+                    DelayTime::Synced {
+                        division: NoteDivision::QUARTER,
+                        bpm: 120.0,
+                    }
+                } else {
+                    DelayTime::Free(SampleCount::new(
+                        params.time.samples(clock.sample_rate) as u32
+                    ))
+                };
+            }
+
+            match &mut params.time {
+                DelayTime::Free(time) => {
+                    ui.add(
+                        time.widget(
+                            clock,
+                            Some((SampleCount::new(1), SampleCount::new(SIZE as u32))),
+                        )
+                        .text("Time"),
+                    );
+                }
+                DelayTime::Synced { division, bpm } => {
+                    use crate::sample::time::DivisionModifier;
+
+                    ui.add(egui::Slider::new(bpm, 20.0..=300.0).text("BPM"));
+
+                    ui.horizontal(|ui| {
+                        [1, 2, 4, 8, 16, 32].into_iter().for_each(|denominator| {
+                            ui.radio_value(
+                                &mut division.denominator,
+                                denominator,
+                                format!("1/{denominator}"),
+                            );
+                        });
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.radio_value(
+                            &mut division.modifier,
+                            DivisionModifier::Straight,
+                            "Straight",
+                        );
+                        ui.radio_value(&mut division.modifier, DivisionModifier::Dotted, "Dotted");
+                        ui.radio_value(
+                            &mut division.modifier,
+                            DivisionModifier::Triplet,
+                            "Triplet",
+                        );
+                    });
+                }
+            }
+
+            ui.add(params.feedback.widget().text("Feedback"));
+            ui.add(params.tone.widget().text("Tone"));
+
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut params.kind, DelayKind::Stereo, "Stereo");
+                ui.radio_value(&mut params.kind, DelayKind::PingPong, "Ping-pong");
+            });
+
+            ui.add(params.mix.widget().text("Dry/wet"));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Delay, DelayParams, DelayTime};
+    use crate::{
+        osc::clock::Clock,
+        param::f32::UnitInterval,
+        sample::{time::SampleCount, Frame},
+    };
+
+    #[test]
+    fn impulse_echo() {
+        const TIME: u32 = 100;
+
+        let clock = Clock::zero(48_000);
+        let mut delay = Delay::<256>::new();
+        let params = DelayParams {
+            mix: UnitInterval::MAX,
+            feedback: UnitInterval::EQUILIBRIUM,
+            time: DelayTime::Free(SampleCount::new(TIME)),
+            tone: UnitInterval::MAX,
+            ..DelayParams::new(clock.sample_rate)
+        };
+
+        let output = clock
+            .for_buffer(TIME as usize * 2 + 1)
+            .map(|clock| {
+                let input = if clock.tick == 0 {
+                    Frame::mono(1.0)
+                } else {
+                    Frame::zero()
+                };
+                delay.tick(&clock, input, &params).left().abs()
+            })
+            .collect::<alloc::vec::Vec<_>>();
 
-        mix
+        // First echo is not filtered
+        assert_eq!(output[TIME as usize], 1.0);
+        assert!(output[..TIME as usize].iter().all(|sample| *sample == 0.0));
+        // Second echo is attenuated by feedback and tone, and smeared by tone filter
+        assert!(output[2 * TIME as usize] < 0.5);
+        assert!(output[2 * TIME as usize] > 0.0);
     }
 }
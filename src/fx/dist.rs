@@ -1,7 +1,21 @@
-use crate::{param::f32::UnitInterval, sample::Frame};
+use super::{
+    filter::svf::{Svf, SvfMode},
+    Fx,
+};
+use crate::{
+    midi::event::MidiEventListener, osc::clock::Clock, param::f32::UnitInterval, sample::Frame,
+};
 // use micromath::F32Ext as _;
 use num_traits::Float;
 
+/// Input gain at maximum drive (about +30dB)
+pub const DIST_MAX_DRIVE_GAIN: f32 = 32.0;
+
+/// Tone filter cutoff range, exponential in between
+pub const DIST_TONE_MIN_CUTOFF: f32 = 500.0;
+pub const DIST_TONE_MAX_CUTOFF: f32 = 20_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DistKind {
     HardClip,
     SoftClip,
@@ -9,21 +23,57 @@ pub enum DistKind {
     HalfWaveRect,
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct DistParams {
     pub kind: DistKind,
-    pub input: UnitInterval,
+    /// Input gain from unity to [`DIST_MAX_DRIVE_GAIN`]
+    pub drive: UnitInterval,
+    /// Cutoff of the low-pass taming harmonics after distortion
+    pub tone: UnitInterval,
+}
+
+impl Default for DistParams {
+    fn default() -> Self {
+        Self {
+            kind: DistKind::SoftClip,
+            drive: UnitInterval::MIN,
+            tone: UnitInterval::MAX,
+        }
+    }
 }
 
-// TODO: Filters
-pub struct Dist {}
+impl DistParams {
+    #[inline]
+    pub fn drive_gain(&self) -> f32 {
+        1.0 + self.drive.inner() * (DIST_MAX_DRIVE_GAIN - 1.0)
+    }
+
+    #[inline]
+    pub fn tone_cutoff(&self) -> f32 {
+        DIST_TONE_MIN_CUTOFF * (DIST_TONE_MAX_CUTOFF / DIST_TONE_MIN_CUTOFF).powf(self.tone.inner())
+    }
+}
+
+pub struct Dist {
+    /// Post-distortion tone filter
+    flt: Frame<Svf>,
+}
+
+impl Default for Dist {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Dist {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            flt: Frame::from_fn(|_| Svf::new()),
+        }
     }
 
-    pub fn tick(&mut self, input: Frame, params: &DistParams) -> Frame {
-        let input = input.map(|input| input * params.input.inner());
+    pub fn tick(&mut self, clock: &Clock, input: Frame, params: &DistParams) -> Frame {
+        let input = input * params.drive_gain();
 
         let output = match params.kind {
             DistKind::HardClip => {
@@ -68,6 +118,73 @@ impl Dist {
             DistKind::HalfWaveRect => input.map(|input| if input > 0.0 { input } else { 0.0 }),
         };
 
-        output
+        let tone_cutoff = params.tone_cutoff();
+        output.zip_mut(&mut self.flt, |sample, flt| {
+            flt.set_params(tone_cutoff, UnitInterval::MIN, clock.sample_rate);
+            flt.process_mode(*sample, SvfMode::LowPass)
+        })
+    }
+}
+
+/// [`Dist`] effect owning its parameters
+#[derive(Default)]
+pub struct DistFx {
+    dist: Dist,
+    params: DistParams,
+}
+
+impl DistFx {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn params_mut(&mut self) -> &mut DistParams {
+        &mut self.params
+    }
+}
+
+impl MidiEventListener for DistFx {
+    #[inline]
+    fn note_on(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
+        let _ = clock;
+        let _ = note;
+        let _ = velocity;
+    }
+
+    #[inline]
+    fn note_off(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
+        let _ = clock;
+        let _ = note;
+        let _ = velocity;
+    }
+}
+
+impl Fx for DistFx {
+    #[inline]
+    fn tick(&mut self, clock: &Clock, input: Frame) -> Frame {
+        self.dist.tick(clock, input, &self.params)
+    }
+
+    #[inline]
+    fn name(&self) -> &str {
+        "Dist"
+    }
+
+    #[cfg(feature = "egui")]
+    fn egui(&mut self, ui: &mut egui::Ui, _params: (Clock,)) {
+        ui.vertical(|ui| {
+            ui.label(self.name());
+
+            let params = &mut self.params;
+
+            ui.radio_value(&mut params.kind, DistKind::HardClip, "Hard clip");
+            ui.radio_value(&mut params.kind, DistKind::SoftClip, "Soft clip");
+            ui.radio_value(&mut params.kind, DistKind::Exp, "Exp");
+            ui.radio_value(&mut params.kind, DistKind::HalfWaveRect, "Half-wave rect");
+
+            ui.add(params.drive.widget().text("Drive"));
+            ui.add(params.tone.widget().text("Tone"));
+        });
     }
 }
@@ -0,0 +1,85 @@
+use super::svf::clamp_cutoff;
+use crate::param::f32::UnitInterval;
+#[allow(unused)]
+use num_traits::Float as _;
+
+/// Input gain at maximum drive
+pub const LADDER_MAX_DRIVE_GAIN: f32 = 16.0;
+
+/// Feedback amount at maximum resonance. The ladder self-oscillates at 4.0.
+const LADDER_MAX_FEEDBACK: f32 = 3.9;
+
+/// 4-pole (24dB/oct) low-pass ladder built of zero-delay feedback one-pole stages with saturated input. Like [`super::svf::Svf`], safe to modulate every sample.
+#[derive(Debug, Clone, Copy)]
+pub struct Ladder {
+    stages: [f32; 4],
+
+    // Coefficients //
+    /// One-pole stage gain `g / (1 + g)`
+    gain: f32,
+    /// Feedback amount
+    k: f32,
+
+    tuned: Option<(f32, UnitInterval, u32)>,
+}
+
+impl Default for Ladder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ladder {
+    pub const fn new() -> Self {
+        Self {
+            stages: [0.0; 4],
+            gain: 0.0,
+            k: 0.0,
+            tuned: None,
+        }
+    }
+
+    #[inline]
+    pub fn set_params(&mut self, cutoff: f32, resonance: UnitInterval, sample_rate: u32) {
+        if self.tuned == Some((cutoff, resonance, sample_rate)) {
+            return;
+        }
+        self.tuned = Some((cutoff, resonance, sample_rate));
+
+        let g =
+            (core::f32::consts::PI * clamp_cutoff(cutoff, sample_rate) / sample_rate as f32).tan();
+        self.gain = g / (1.0 + g);
+        self.k = LADDER_MAX_FEEDBACK * resonance.inner();
+    }
+
+    /// Process a sample, `drive` is the input gain from unity to [`LADDER_MAX_DRIVE_GAIN`]
+    #[inline]
+    pub fn process(&mut self, sample: f32, drive: UnitInterval) -> f32 {
+        let gain = self.gain;
+        let input = (sample * (1.0 + drive.inner() * (LADDER_MAX_DRIVE_GAIN - 1.0))).tanh();
+
+        // Resolve zero-delay feedback: the last stage output is `gain^4 * u + s`, where `s` is the contribution of stages states
+        let s = self
+            .stages
+            .iter()
+            .fold(0.0, |s, state| s * gain + state * (1.0 - gain));
+        let gain4 = gain * gain * gain * gain;
+
+        let u = ((input - self.k * s) / (1.0 + self.k * gain4)).tanh();
+
+        let output = self.stages.iter_mut().fold(u, |input, state| {
+            let v = (input - *state) * gain;
+            let output = v + *state;
+            *state = output + v;
+            output
+        });
+
+        // Compensate passband loss caused by feedback
+        output * (1.0 + self.k)
+    }
+
+    #[inline]
+    pub fn reset(&mut self) {
+        self.stages = [0.0; 4];
+    }
+}
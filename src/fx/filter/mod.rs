@@ -1 +1,196 @@
+use super::Fx;
+use crate::{
+    midi::event::MidiEventListener,
+    osc::clock::{Clock, Freq},
+    param::f32::UnitInterval,
+    sample::Frame,
+};
+use ladder::Ladder;
+use svf::{Svf, SvfMode};
+
+pub mod ladder;
 pub mod one_pole;
+pub mod svf;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterKind {
+    Svf(SvfMode),
+    /// 4-pole low-pass ladder
+    Ladder,
+}
+
+impl Default for FilterKind {
+    fn default() -> Self {
+        Self::Svf(SvfMode::LowPass)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FilterParams {
+    pub kind: FilterKind,
+    pub cutoff: Freq,
+    pub resonance: UnitInterval,
+    /// Input saturation, only used by the ladder
+    pub drive: UnitInterval,
+}
+
+impl Default for FilterParams {
+    fn default() -> Self {
+        Self {
+            kind: FilterKind::default(),
+            cutoff: Freq::kHz(2),
+            resonance: UnitInterval::MIN,
+            drive: UnitInterval::MIN,
+        }
+    }
+}
+
+/// Mono filter of any [`FilterKind`], a building block for components filtering a single signal
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Filter {
+    svf: Svf,
+    ladder: Ladder,
+}
+
+impl Filter {
+    pub const fn new() -> Self {
+        Self {
+            svf: Svf::new(),
+            ladder: Ladder::new(),
+        }
+    }
+
+    #[inline]
+    pub fn process(&mut self, sample_rate: u32, sample: f32, params: &FilterParams) -> f32 {
+        match params.kind {
+            FilterKind::Svf(mode) => {
+                self.svf
+                    .set_params(params.cutoff.inner(), params.resonance, sample_rate);
+                self.svf.process_mode(sample, mode)
+            }
+            FilterKind::Ladder => {
+                self.ladder
+                    .set_params(params.cutoff.inner(), params.resonance, sample_rate);
+                self.ladder.process(sample, params.drive)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn reset(&mut self) {
+        self.svf.reset();
+        self.ladder.reset();
+    }
+}
+
+/// Stereo [`Filter`] effect owning its parameters
+pub struct FilterFx {
+    filters: Frame<Filter>,
+    params: FilterParams,
+}
+
+impl Default for FilterFx {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FilterFx {
+    pub fn new() -> Self {
+        Self {
+            filters: Frame::from_fn(|_| Filter::new()),
+            params: FilterParams::default(),
+        }
+    }
+
+    #[inline]
+    pub fn params_mut(&mut self) -> &mut FilterParams {
+        &mut self.params
+    }
+}
+
+impl MidiEventListener for FilterFx {
+    #[inline]
+    fn note_on(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
+        let _ = clock;
+        let _ = note;
+        let _ = velocity;
+    }
+
+    #[inline]
+    fn note_off(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
+        let _ = clock;
+        let _ = note;
+        let _ = velocity;
+    }
+}
+
+impl Fx for FilterFx {
+    #[inline]
+    fn tick(&mut self, clock: &Clock, input: Frame) -> Frame {
+        input.zip_mut(&mut self.filters, |sample, filter| {
+            filter.process(clock.sample_rate, *sample, &self.params)
+        })
+    }
+
+    #[inline]
+    fn name(&self) -> &str {
+        "Filter"
+    }
+
+    #[cfg(feature = "egui")]
+    fn egui(&mut self, ui: &mut egui::Ui, _params: (Clock,)) {
+        ui.vertical(|ui| {
+            ui.label(self.name());
+
+            let params = &mut self.params;
+
+            ui.horizontal_wrapped(|ui| {
+                SvfMode::each().for_each(|mode| {
+                    ui.radio_value(&mut params.kind, FilterKind::Svf(mode), format!("{mode}"));
+                });
+                ui.radio_value(&mut params.kind, FilterKind::Ladder, "Ladder");
+            });
+
+            ui.add(
+                params
+                    .cutoff
+                    .widget(Some(Freq::Hz(20)..=Freq::kHz(20)))
+                    .text("Cutoff"),
+            );
+            ui.add(params.resonance.widget().text("Resonance"));
+
+            if params.kind == FilterKind::Ladder {
+                ui.add(params.drive.widget().text("Drive"));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn settle(kind: FilterKind, input: f32) -> f32 {
+        let params = FilterParams {
+            kind,
+            cutoff: Freq::kHz(1),
+            resonance: UnitInterval::EQUILIBRIUM,
+            drive: UnitInterval::MIN,
+        };
+        let mut filter = Filter::new();
+
+        (0..SAMPLE_RATE / 10).fold(0.0, |_, _| filter.process(SAMPLE_RATE, input, &params))
+    }
+
+    #[test]
+    fn dc_response() {
+        assert!((settle(FilterKind::Svf(SvfMode::LowPass), 0.5) - 0.5).abs() < 1e-3);
+        assert!(settle(FilterKind::Svf(SvfMode::HighPass), 0.5).abs() < 1e-3);
+        assert!(settle(FilterKind::Svf(SvfMode::BandPass), 0.5).abs() < 1e-3);
+        // Feedback loss is compensated, small input is barely saturated
+        assert!((settle(FilterKind::Ladder, 0.1) - 0.1).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::param::f32::UnitInterval;
+#[allow(unused)]
+use num_traits::Float as _;
+
+/// The lowest cutoff filters can be tuned to
+pub const FILTER_MIN_CUTOFF: f32 = 10.0;
+
+/// The highest cutoff relative to sample rate. TPT filters are stable up to Nyquist but warping makes cutoffs right below it useless.
+pub const FILTER_MAX_CUTOFF_RATIO: f32 = 0.49;
+
+/// Clamp cutoff frequency into the range filters can be tuned to at given sample rate
+#[inline]
+pub fn clamp_cutoff(cutoff: f32, sample_rate: u32) -> f32 {
+    cutoff.clamp(
+        FILTER_MIN_CUTOFF,
+        sample_rate as f32 * FILTER_MAX_CUTOFF_RATIO,
+    )
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SvfMode {
+    #[default]
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+    Peak,
+}
+
+impl SvfMode {
+    pub fn each() -> impl Iterator<Item = Self> {
+        [
+            SvfMode::LowPass,
+            SvfMode::HighPass,
+            SvfMode::BandPass,
+            SvfMode::Notch,
+            SvfMode::Peak,
+        ]
+        .into_iter()
+    }
+}
+
+impl core::fmt::Display for SvfMode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SvfMode::LowPass => "LP".fmt(f),
+            SvfMode::HighPass => "HP".fmt(f),
+            SvfMode::BandPass => "BP".fmt(f),
+            SvfMode::Notch => "Notch".fmt(f),
+            SvfMode::Peak => "Peak".fmt(f),
+        }
+    }
+}
+
+/// All outputs of a single [`Svf`] step
+#[derive(Debug, Clone, Copy)]
+pub struct SvfOutput {
+    pub low: f32,
+    pub band: f32,
+    pub high: f32,
+}
+
+impl SvfOutput {
+    #[inline]
+    pub fn mode(&self, mode: SvfMode) -> f32 {
+        match mode {
+            SvfMode::LowPass => self.low,
+            SvfMode::HighPass => self.high,
+            SvfMode::BandPass => self.band,
+            SvfMode::Notch => self.low + self.high,
+            SvfMode::Peak => self.low - self.high,
+        }
+    }
+}
+
+/// Topology-preserving transform (zero-delay feedback) state-variable filter. The structure stays stable when cutoff and resonance change every sample, so it can be modulated directly.
+#[derive(Debug, Clone, Copy)]
+pub struct Svf {
+    ic1eq: f32,
+    ic2eq: f32,
+
+    // Coefficients //
+    k: f32,
+    a1: f32,
+    a2: f32,
+    a3: f32,
+
+    /// Parameters coefficients were computed for, to skip recomputation when they don't change
+    tuned: Option<(f32, UnitInterval, u32)>,
+}
+
+impl Default for Svf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Svf {
+    pub const fn new() -> Self {
+        Self {
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+            k: 2.0,
+            a1: 1.0,
+            a2: 0.0,
+            a3: 0.0,
+            tuned: None,
+        }
+    }
+
+    /// Tune the filter. Resonance maps from no resonance (Q of 0.5) to the edge of self-oscillation.
+    #[inline]
+    pub fn set_params(&mut self, cutoff: f32, resonance: UnitInterval, sample_rate: u32) {
+        if self.tuned == Some((cutoff, resonance, sample_rate)) {
+            return;
+        }
+        self.tuned = Some((cutoff, resonance, sample_rate));
+
+        let g =
+            (core::f32::consts::PI * clamp_cutoff(cutoff, sample_rate) / sample_rate as f32).tan();
+        self.k = 2.0 - 1.98 * resonance.inner();
+        self.a1 = 1.0 / (1.0 + g * (g + self.k));
+        self.a2 = g * self.a1;
+        self.a3 = g * self.a2;
+    }
+
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> SvfOutput {
+        let v3 = sample - self.ic2eq;
+        let v1 = self.a1 * self.ic1eq + self.a2 * v3;
+        let v2 = self.ic2eq + self.a2 * self.ic1eq + self.a3 * v3;
+
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+
+        SvfOutput {
+            low: v2,
+            band: v1,
+            high: sample - self.k * v1 - v2,
+        }
+    }
+
+    #[inline]
+    pub fn process_mode(&mut self, sample: f32, mode: SvfMode) -> f32 {
+        self.process(sample).mode(mode)
+    }
+
+    #[inline]
+    pub fn reset(&mut self) {
+        self.ic1eq = 0.0;
+        self.ic2eq = 0.0;
+    }
+}
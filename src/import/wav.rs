@@ -0,0 +1,326 @@
+use crate::export::wav::{WavFormat, WavSpec};
+
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavReadError {
+    /// No `RIFF`/`WAVE` header
+    NotWav,
+    /// The file ends in the middle of a chunk
+    UnexpectedEnd,
+    /// No `fmt ` chunk before the `data` chunk
+    MissingFormat,
+    MissingData,
+    /// Only 16 and 24 bit PCM and 32 bit float samples are supported
+    UnsupportedFormat {
+        format_tag: u16,
+        bits_per_sample: u16,
+    },
+    /// Zero channels or block alignment not matching the format
+    InvalidFormat,
+}
+
+/// Little-endian chunk cursor
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.offset >= self.bytes.len()
+    }
+
+    #[inline]
+    fn take(&mut self, len: usize) -> Result<&'a [u8], WavReadError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .ok_or(WavReadError::UnexpectedEnd)?;
+        let bytes = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or(WavReadError::UnexpectedEnd)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    #[inline]
+    fn u16(&mut self) -> Result<u16, WavReadError> {
+        self.take(2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    #[inline]
+    fn u32(&mut self) -> Result<u32, WavReadError> {
+        self.take(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// WAV decoder over a file loaded into memory. Samples of all channels are averaged into mono.
+#[derive(Debug, Clone, Copy)]
+pub struct WavReader<'a> {
+    spec: WavSpec,
+    channels: u16,
+    data: &'a [u8],
+    cycle_len: Option<usize>,
+}
+
+impl<'a> WavReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, WavReadError> {
+        let mut reader = Reader::new(bytes);
+
+        if reader.take(4) != Ok(b"RIFF") {
+            return Err(WavReadError::NotWav);
+        }
+        let _riff_len = reader.u32()?;
+        if reader.take(4) != Ok(b"WAVE") {
+            return Err(WavReadError::NotWav);
+        }
+
+        let mut format = None;
+        let mut cycle_len = None;
+        while !reader.is_empty() {
+            let kind = reader.take(4)?;
+            let len = reader.u32()? as usize;
+            let chunk = reader.take(len)?;
+            // Chunks are word aligned, the padding byte may be missing at the end of the file
+            if len % 2 == 1 && !reader.is_empty() {
+                reader.take(1)?;
+            }
+
+            match kind {
+                b"fmt " => format = Some(read_format(chunk)?),
+                b"clm " => cycle_len = read_cycle_len(chunk),
+                b"data" => {
+                    let (spec, channels) = format.ok_or(WavReadError::MissingFormat)?;
+
+                    return Ok(Self {
+                        spec,
+                        channels,
+                        data: chunk,
+                        cycle_len,
+                    });
+                }
+                // Unknown chunks must be skipped
+                _ => {}
+            }
+        }
+
+        Err(WavReadError::MissingData)
+    }
+
+    #[inline]
+    pub fn spec(&self) -> WavSpec {
+        self.spec
+    }
+
+    #[inline]
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Count of frames
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len() / self.block_align()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Samples per single-cycle frame declared by the `clm ` chunk Serum writes into wavetable files. Only chunks before `data` are read.
+    #[inline]
+    pub fn cycle_len(&self) -> Option<usize> {
+        self.cycle_len
+    }
+
+    /// Mono samples, channels of each frame are averaged
+    pub fn samples(&self) -> impl Iterator<Item = f32> + 'a {
+        let format = self.spec.format;
+        let channels = self.channels as usize;
+
+        self.data
+            .chunks_exact(self.block_align())
+            .map(move |frame| {
+                frame
+                    .chunks_exact(format.bytes_per_sample())
+                    .map(|sample| format.decode(sample))
+                    .sum::<f32>()
+                    / channels as f32
+            })
+    }
+
+    #[inline]
+    fn block_align(&self) -> usize {
+        self.spec.format.bytes_per_sample() * self.channels as usize
+    }
+}
+
+fn read_format(chunk: &[u8]) -> Result<(WavSpec, u16), WavReadError> {
+    let mut reader = Reader::new(chunk);
+
+    let mut format_tag = reader.u16()?;
+    let channels = reader.u16()?;
+    let sample_rate = reader.u32()?;
+    let _byte_rate = reader.u32()?;
+    let block_align = reader.u16()?;
+    let bits_per_sample = reader.u16()?;
+
+    if format_tag == WAVE_FORMAT_EXTENSIBLE {
+        // cbSize, valid bits per sample and channel mask precede the sub-format GUID starting with the actual format tag
+        reader.take(2 + 2 + 4)?;
+        format_tag = reader.u16()?;
+    }
+
+    let format = WavFormat::from_tag(format_tag, bits_per_sample).ok_or(
+        WavReadError::UnsupportedFormat {
+            format_tag,
+            bits_per_sample,
+        },
+    )?;
+
+    if channels == 0 || block_align as usize != format.bytes_per_sample() * channels as usize {
+        return Err(WavReadError::InvalidFormat);
+    }
+
+    Ok((WavSpec::new(sample_rate, format), channels))
+}
+
+/// Cycle length of Serum `clm ` chunk, text like `<!>2048 01000000 wavetable (www.xferrecords.com)`
+fn read_cycle_len(chunk: &[u8]) -> Option<usize> {
+    let digits = chunk.strip_prefix(b"<!>")?;
+
+    digits
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit())
+        .try_fold(None, |len: Option<usize>, byte| {
+            len.unwrap_or(0)
+                .checked_mul(10)?
+                .checked_add((byte - b'0') as usize)
+                .map(Some)
+        })
+        .flatten()
+        .filter(|&len| len > 0)
+}
+
+/// Mono 32 bit float file with an optional Serum `clm ` chunk
+#[cfg(test)]
+pub(crate) fn mono_float_wav(
+    sample_rate: u32,
+    cycle_len: Option<usize>,
+    samples: &[f32],
+) -> alloc::vec::Vec<u8> {
+    use crate::export::wav::WAVE_FORMAT_IEEE_FLOAT;
+    use alloc::{format, vec::Vec};
+
+    let mut chunks = Vec::new();
+    let mut chunk = |kind: &[u8], body: &[u8]| {
+        chunks.extend_from_slice(kind);
+        chunks.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        chunks.extend_from_slice(body);
+        if body.len() % 2 == 1 {
+            chunks.push(0);
+        }
+    };
+
+    let fmt = [
+        &WAVE_FORMAT_IEEE_FLOAT.to_le_bytes()[..],
+        &1u16.to_le_bytes(),
+        &sample_rate.to_le_bytes(),
+        &(sample_rate * 4).to_le_bytes(),
+        &4u16.to_le_bytes(),
+        &32u16.to_le_bytes(),
+    ]
+    .concat();
+    chunk(b"fmt ", &fmt);
+
+    if let Some(cycle_len) = cycle_len {
+        chunk(
+            b"clm ",
+            format!("<!>{cycle_len} 10000000 wavetable (www.xferrecords.com)").as_bytes(),
+        );
+    }
+
+    let data = samples
+        .iter()
+        .flat_map(|sample| sample.to_le_bytes())
+        .collect::<Vec<_>>();
+    chunk(b"data", &data);
+
+    [
+        &b"RIFF"[..],
+        &(chunks.len() as u32 + 4).to_le_bytes(),
+        b"WAVE",
+        &chunks,
+    ]
+    .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mono_float_wav, WavReadError, WavReader};
+    use crate::{
+        export::wav::{write_wav, WavFormat, WavSpec},
+        sample::Frame,
+    };
+    use alloc::vec::Vec;
+
+    #[test]
+    fn read_exported() {
+        let frames = (0..100)
+            .map(|index| Frame::stereo(index as f32 / 100.0, index as f32 / 200.0))
+            .collect::<Vec<_>>();
+
+        [WavFormat::Pcm16, WavFormat::Pcm24, WavFormat::Float32]
+            .into_iter()
+            .for_each(|format| {
+                let spec = WavSpec::new(44_100, format);
+                let bytes = write_wav(Vec::new(), spec, &frames).unwrap();
+                let reader = WavReader::new(&bytes).unwrap();
+
+                assert_eq!(reader.spec(), spec);
+                assert_eq!(reader.channels(), 2);
+                assert_eq!(reader.len(), frames.len());
+                assert_eq!(reader.cycle_len(), None);
+
+                reader
+                    .samples()
+                    .zip(frames.iter())
+                    .for_each(|(sample, frame)| {
+                        let expected = (frame.left() + frame.right()) / 2.0;
+                        assert!((sample - expected).abs() < 1e-4, "{format:?}");
+                    });
+            });
+    }
+
+    #[test]
+    fn serum_cycle_len() {
+        let bytes = mono_float_wav(48_000, Some(256), &[0.5]);
+
+        let reader = WavReader::new(&bytes).unwrap();
+        assert_eq!(reader.cycle_len(), Some(256));
+        assert_eq!(reader.channels(), 1);
+        assert_eq!(reader.spec().sample_rate, 48_000);
+        assert_eq!(reader.samples().collect::<Vec<_>>(), [0.5]);
+
+        assert_eq!(
+            WavReader::new(&bytes[..bytes.len() - 2]).unwrap_err(),
+            WavReadError::UnexpectedEnd
+        );
+        assert_eq!(WavReader::new(b"RIFX").unwrap_err(), WavReadError::NotWav);
+        assert_eq!(
+            WavReader::new(b"RIFF\x0C\0\0\0WAVEdata\xFF\xFF\xFF\xFF").unwrap_err(),
+            WavReadError::UnexpectedEnd
+        );
+    }
+}
@@ -14,6 +14,7 @@ pub mod buffer;
 pub mod daw;
 pub mod export;
 pub mod fx;
+pub mod import;
 pub mod macros;
 pub mod midi;
 pub mod modx;
@@ -1,8 +1,144 @@
-use crate::{osc::clock::Clock, param::f32::UnitInterval};
+use crate::{
+    osc::clock::Clock,
+    param::f32::{SignedUnitInterval, UnitInterval},
+};
 
 use super::note::Note;
 
 pub trait MidiEventListener {
     fn note_on(&mut self, clock: &Clock, note: Note, velocity: UnitInterval);
     fn note_off(&mut self, clock: &Clock, note: Note, velocity: UnitInterval);
+
+    #[inline]
+    fn pitch_bend(&mut self, clock: &Clock, bend: SignedUnitInterval) {
+        let _ = clock;
+        let _ = bend;
+    }
+
+    #[inline]
+    fn control_change(&mut self, clock: &Clock, controller: u8, value: UnitInterval) {
+        let _ = clock;
+        let _ = controller;
+        let _ = value;
+    }
+
+    #[inline]
+    fn channel_pressure(&mut self, clock: &Clock, pressure: UnitInterval) {
+        let _ = clock;
+        let _ = pressure;
+    }
+
+    #[inline]
+    fn poly_pressure(&mut self, clock: &Clock, note: Note, pressure: UnitInterval) {
+        let _ = clock;
+        let _ = note;
+        let _ = pressure;
+    }
+
+    #[inline]
+    fn program_change(&mut self, clock: &Clock, program: u8) {
+        let _ = clock;
+        let _ = program;
+    }
+}
+
+/// Count of MIDI 1.0 channels
+pub const MIDI_CHANNELS: u8 = 16;
+
+/// Modulation wheel controller number
+pub const MIDI_CC_MOD_WHEEL: u8 = 1;
+
+/// Sustain (damper) pedal controller number, values from the middle up mean the pedal is down
+pub const MIDI_CC_SUSTAIN: u8 = 64;
+
+/// MIDI 1.0 message. Channels are zero-based, 7-bit values are normalized to [`UnitInterval`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MidiEvent<'a> {
+    NoteOff {
+        channel: u8,
+        note: Note,
+        velocity: UnitInterval,
+    },
+    /// Note on with zero velocity is parsed as [`MidiEvent::NoteOff`]
+    NoteOn {
+        channel: u8,
+        note: Note,
+        velocity: UnitInterval,
+    },
+    /// Polyphonic aftertouch
+    PolyPressure {
+        channel: u8,
+        note: Note,
+        pressure: UnitInterval,
+    },
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: UnitInterval,
+    },
+    ProgramChange {
+        channel: u8,
+        program: u8,
+    },
+    /// Channel aftertouch
+    ChannelPressure {
+        channel: u8,
+        pressure: UnitInterval,
+    },
+    PitchBend {
+        channel: u8,
+        bend: SignedUnitInterval,
+    },
+    /// System exclusive message payload without the leading `0xF0` and the trailing `0xF7`
+    SysEx(&'a [u8]),
+    /// Timing clock, sent 24 times per quarter note
+    Clock,
+    Start,
+    Continue,
+    Stop,
+    ActiveSensing,
+    Reset,
+}
+
+impl<'a> MidiEvent<'a> {
+    /// Channel of a channel message, `None` for system messages
+    #[inline]
+    pub fn channel(&self) -> Option<u8> {
+        match *self {
+            MidiEvent::NoteOff { channel, .. }
+            | MidiEvent::NoteOn { channel, .. }
+            | MidiEvent::PolyPressure { channel, .. }
+            | MidiEvent::ControlChange { channel, .. }
+            | MidiEvent::ProgramChange { channel, .. }
+            | MidiEvent::ChannelPressure { channel, .. }
+            | MidiEvent::PitchBend { channel, .. } => Some(channel),
+            MidiEvent::SysEx(_)
+            | MidiEvent::Clock
+            | MidiEvent::Start
+            | MidiEvent::Continue
+            | MidiEvent::Stop
+            | MidiEvent::ActiveSensing
+            | MidiEvent::Reset => None,
+        }
+    }
+}
+
+/// Channels a MIDI input listens to
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MidiChannelFilter {
+    /// Listen to all channels
+    #[default]
+    Omni,
+    Channel(u8),
+}
+
+impl MidiChannelFilter {
+    #[inline]
+    pub fn accepts(&self, event: &MidiEvent) -> bool {
+        match (self, event.channel()) {
+            (MidiChannelFilter::Channel(filter), Some(channel)) => *filter == channel,
+            // System messages are not addressed to a channel
+            _ => true,
+        }
+    }
 }
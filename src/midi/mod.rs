@@ -1,2 +1,4 @@
-pub mod note;
 pub mod event;
+pub mod note;
+pub mod parser;
+pub mod smf;
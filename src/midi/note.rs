@@ -2,7 +2,7 @@ use num::FromPrimitive;
 
 macro_rules! notes {
     ($($name: ident: $freq: expr),* $(,)?) => {
-        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, FromPrimitive, ToPrimitive)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, FromPrimitive, ToPrimitive)]
         #[repr(u8)]
         pub enum Note {
             $($name),*
@@ -22,16 +22,17 @@ macro_rules! notes {
                     $(Self::$name => crate::osc::clock::Freq::new($freq)),*
                 }
             }
-
-            // TODO: FromPrimitive or don't even use this
-            // pub const fn from_midi(midi_note: u8) -> Self {
-            //     unsafe {core::mem::transmute(midi_note)}
-            // }
         }
     };
 }
 
 impl Note {
+    /// Note of MIDI note number, `None` for values out of 7-bit range
+    #[inline]
+    pub fn from_midi(midi_note: u8) -> Option<Self> {
+        FromPrimitive::from_u8(midi_note)
+    }
+
     #[inline]
     pub fn saturating_add(self, transpose: i16) -> Self {
         FromPrimitive::from_i16((self as i16).saturating_add(transpose).clamp(0, 127)).unwrap()
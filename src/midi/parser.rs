@@ -0,0 +1,247 @@
+use super::{event::MidiEvent, note::Note};
+use crate::param::f32::{SignedUnitInterval, UnitInterval};
+
+/// Default capacity of SysEx payload kept by [`MidiParser`]
+pub const MIDI_SYSEX_SIZE: usize = 128;
+
+const SYSEX_START: u8 = 0xF0;
+const SYSEX_END: u8 = 0xF7;
+
+/// Count of data bytes following a status byte
+#[inline]
+pub(super) const fn data_len(status: u8) -> usize {
+    match status & 0xF0 {
+        0xC0 | 0xD0 => 1,
+        0x80..=0xE0 => 2,
+        _ => match status {
+            // MTC quarter frame, song select
+            0xF1 | 0xF3 => 1,
+            // Song position pointer
+            0xF2 => 2,
+            _ => 0,
+        },
+    }
+}
+
+#[inline]
+fn normalized(value: u8) -> UnitInterval {
+    UnitInterval::new_checked(value as f32 / 127.0)
+}
+
+/// Decode a complete channel message, `None` for system messages
+#[inline]
+pub(super) fn channel_message(status: u8, [first, second]: [u8; 2]) -> Option<MidiEvent<'static>> {
+    let channel = status & 0x0F;
+    let note = || Note::from_midi(first);
+
+    match status & 0xF0 {
+        0x80 => Some(MidiEvent::NoteOff {
+            channel,
+            note: note()?,
+            velocity: normalized(second),
+        }),
+        0x90 if second == 0 => Some(MidiEvent::NoteOff {
+            channel,
+            note: note()?,
+            velocity: UnitInterval::MIN,
+        }),
+        0x90 => Some(MidiEvent::NoteOn {
+            channel,
+            note: note()?,
+            velocity: normalized(second),
+        }),
+        0xA0 => Some(MidiEvent::PolyPressure {
+            channel,
+            note: note()?,
+            pressure: normalized(second),
+        }),
+        0xB0 => Some(MidiEvent::ControlChange {
+            channel,
+            controller: first,
+            value: normalized(second),
+        }),
+        0xC0 => Some(MidiEvent::ProgramChange {
+            channel,
+            program: first,
+        }),
+        0xD0 => Some(MidiEvent::ChannelPressure {
+            channel,
+            pressure: normalized(first),
+        }),
+        0xE0 => {
+            let value = ((second as i16) << 7 | first as i16) - 0x2000;
+            Some(MidiEvent::PitchBend {
+                channel,
+                bend: SignedUnitInterval::new(value as f32 / 0x1FFF as f32),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Streaming MIDI 1.0 parser fed byte by byte, e.g. from UART or USB-MIDI or a file. Handles running status and realtime messages interleaved into other messages. SysEx payloads longer than `SYSEX_SIZE` are truncated, system common messages are skipped.
+#[derive(Debug, Clone)]
+pub struct MidiParser<const SYSEX_SIZE: usize = MIDI_SYSEX_SIZE> {
+    /// Status of the message being received, kept as running status for channel messages
+    status: Option<u8>,
+    data: [u8; 2],
+    received: usize,
+    /// Length of SysEx payload received so far, `None` outside of SysEx
+    sysex_len: Option<usize>,
+    sysex: [u8; SYSEX_SIZE],
+}
+
+impl<const SYSEX_SIZE: usize> Default for MidiParser<SYSEX_SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SYSEX_SIZE: usize> MidiParser<SYSEX_SIZE> {
+    pub const fn new() -> Self {
+        Self {
+            status: None,
+            data: [0; 2],
+            received: 0,
+            sysex_len: None,
+            sysex: [0; SYSEX_SIZE],
+        }
+    }
+
+    /// Feed a byte, returning an event if the byte completes one
+    pub fn parse(&mut self, byte: u8) -> Option<MidiEvent<'_>> {
+        match byte {
+            // Realtime messages don't interrupt running status or SysEx
+            0xF8..=0xFF => Self::realtime(byte),
+            SYSEX_START => {
+                self.status = None;
+                self.sysex_len = Some(0);
+                None
+            }
+            SYSEX_END => {
+                self.status = None;
+                self.sysex_len
+                    .take()
+                    .map(|len| MidiEvent::SysEx(&self.sysex[..len]))
+            }
+            0x80..=0xF6 => {
+                // Unterminated SysEx is dropped
+                self.sysex_len = None;
+                self.received = 0;
+                self.status = Some(byte);
+
+                // Tune request has no data
+                if byte == 0xF6 {
+                    self.status = None;
+                }
+
+                None
+            }
+            _ => {
+                if let Some(len) = &mut self.sysex_len {
+                    if let Some(slot) = self.sysex.get_mut(*len) {
+                        *slot = byte;
+                        *len += 1;
+                    }
+                    return None;
+                }
+
+                // Data without status is ignored until the next status byte
+                let status = self.status?;
+
+                self.data[self.received] = byte;
+                self.received += 1;
+
+                if self.received < data_len(status) {
+                    return None;
+                }
+                self.received = 0;
+
+                if status >= 0xF0 {
+                    self.status = None;
+                    return None;
+                }
+
+                channel_message(status, self.data)
+            }
+        }
+    }
+
+    /// Parse all the bytes calling `f` for every complete event
+    pub fn parse_bytes(&mut self, bytes: &[u8], mut f: impl FnMut(MidiEvent)) {
+        bytes.iter().for_each(|&byte| {
+            if let Some(event) = self.parse(byte) {
+                f(event);
+            }
+        });
+    }
+
+    #[inline]
+    fn realtime(byte: u8) -> Option<MidiEvent<'static>> {
+        match byte {
+            0xF8 => Some(MidiEvent::Clock),
+            0xFA => Some(MidiEvent::Start),
+            0xFB => Some(MidiEvent::Continue),
+            0xFC => Some(MidiEvent::Stop),
+            0xFE => Some(MidiEvent::ActiveSensing),
+            0xFF => Some(MidiEvent::Reset),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MidiParser;
+    use crate::{
+        midi::{event::MidiEvent, note::Note},
+        param::f32::{SignedUnitInterval, UnitInterval},
+    };
+
+    #[test]
+    fn running_status() {
+        let mut parser = MidiParser::<4>::new();
+        let note_on = |note| MidiEvent::NoteOn {
+            channel: 1,
+            note,
+            velocity: UnitInterval::MAX,
+        };
+
+        assert_eq!(parser.parse(0x91), None);
+        assert_eq!(parser.parse(60), None);
+        assert_eq!(parser.parse(127), Some(note_on(Note::C4)));
+
+        // Running status with realtime message interleaved
+        assert_eq!(parser.parse(64), None);
+        assert_eq!(parser.parse(0xF8), Some(MidiEvent::Clock));
+        assert_eq!(parser.parse(127), Some(note_on(Note::E4)));
+
+        assert_eq!(parser.parse(60), None);
+        assert_eq!(
+            parser.parse(0),
+            Some(MidiEvent::NoteOff {
+                channel: 1,
+                note: Note::C4,
+                velocity: UnitInterval::MIN
+            })
+        );
+
+        // SysEx is truncated to the parser capacity and cancels running status
+        [0xF0, 1, 2, 3, 4, 5]
+            .into_iter()
+            .for_each(|byte| assert_eq!(parser.parse(byte), None));
+        assert_eq!(parser.parse(0xF7), Some(MidiEvent::SysEx(&[1, 2, 3, 4])));
+        assert_eq!(parser.parse(64), None);
+        assert_eq!(parser.parse(0), None);
+
+        assert_eq!(parser.parse(0xE0), None);
+        assert_eq!(parser.parse(0x7F), None);
+        assert_eq!(
+            parser.parse(0x7F),
+            Some(MidiEvent::PitchBend {
+                channel: 0,
+                bend: SignedUnitInterval::MAX
+            })
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use super::{
+    event::MidiEvent,
+    parser::{channel_message, data_len},
+};
+use crate::{daw::render::NoteEvent, osc::clock::Tick};
+use alloc::vec::Vec;
+#[allow(unused)]
+use num_traits::Float as _;
+
+/// Tempo of a file until the first tempo event, 120 BPM
+pub const SMF_DEFAULT_TEMPO: u32 = 500_000;
+
+const META_EVENT: u8 = 0xFF;
+const META_END_OF_TRACK: u8 = 0x2F;
+const META_TEMPO: u8 = 0x51;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmfError {
+    /// No `MThd` header
+    NotSmf,
+    /// Only type 0 and type 1 files are supported
+    UnsupportedFormat(u16),
+    /// The file ends in the middle of a chunk or an event
+    UnexpectedEnd,
+    /// Malformed event, e.g. data without running status
+    InvalidEvent { track: usize, offset: usize },
+    /// Zero pulses per quarter note, or SMPTE division of an unknown frame rate or zero pulses per frame
+    InvalidDivision(u16),
+}
+
+/// Track event with time in pulses (ticks of the file time division)
+enum TimedMessage {
+    /// Microseconds per quarter note
+    Tempo(u32),
+    Note(NoteEvent),
+}
+
+/// Byte cursor over a file with big-endian and variable-length quantity readers
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    #[inline]
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.offset >= self.bytes.len()
+    }
+
+    #[inline]
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SmfError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .ok_or(SmfError::UnexpectedEnd)?;
+        let bytes = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or(SmfError::UnexpectedEnd)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    #[inline]
+    fn peek(&self) -> Result<u8, SmfError> {
+        self.bytes
+            .get(self.offset)
+            .copied()
+            .ok_or(SmfError::UnexpectedEnd)
+    }
+
+    #[inline]
+    fn u8(&mut self) -> Result<u8, SmfError> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    #[inline]
+    fn u16(&mut self) -> Result<u16, SmfError> {
+        self.take(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    #[inline]
+    fn u32(&mut self) -> Result<u32, SmfError> {
+        self.take(4)
+            .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Variable-length quantity of up to 4 bytes, a longer one is an invalid event of `track`
+    #[inline]
+    fn vlq(&mut self, track: usize) -> Result<u32, SmfError> {
+        let offset = self.offset;
+        let mut value = 0;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = value << 7 | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SmfError::InvalidEvent { track, offset })
+    }
+}
+
+/// Read type 0 or type 1 Standard MIDI File into note events sorted by tick. Event ticks are samples at `sample_rate` from the start of the file, following the tempo map. Each event is addressed to the rack channel of its MIDI channel, see [`NoteEvent::with_channel`]. Other messages are skipped.
+pub fn read_smf(bytes: &[u8], sample_rate: u32) -> Result<Vec<NoteEvent>, SmfError> {
+    let mut reader = Reader::new(bytes);
+
+    if reader.take(4) != Ok(b"MThd") {
+        return Err(SmfError::NotSmf);
+    }
+    let header_len = reader.u32()? as usize;
+    let mut header = Reader::new(reader.take(header_len)?);
+    let format = header.u16()?;
+    let _tracks = header.u16()?;
+    let division = header.u16()?;
+
+    if format > 1 {
+        return Err(SmfError::UnsupportedFormat(format));
+    }
+
+    let valid_division = if division & 0x8000 == 0 {
+        division > 0
+    } else {
+        matches!((division >> 8) as u8 as i8, -24 | -25 | -29 | -30) && division & 0xFF > 0
+    };
+    if !valid_division {
+        return Err(SmfError::InvalidDivision(division));
+    }
+
+    let mut messages = Vec::new();
+    let mut track = 0;
+    while !reader.is_empty() {
+        let kind = reader.take(4)?;
+        let len = reader.u32()? as usize;
+        let chunk = reader.take(len)?;
+
+        // Unknown chunks must be skipped
+        if kind == b"MTrk" {
+            read_track(track, chunk, &mut messages)?;
+            track += 1;
+        }
+    }
+
+    // Tracks are merged keeping the order of simultaneous events
+    messages.sort_by_key(|(pulse, _)| *pulse);
+
+    let samples_per_pulse = |tempo: u32| -> f64 {
+        if division & 0x8000 == 0 {
+            tempo as f64 / 1_000_000.0 * sample_rate as f64 / division as f64
+        } else {
+            // SMPTE time division is frames per second and pulses per frame, tempo does not apply
+            let fps = match (division >> 8) as u8 as i8 {
+                -29 => 29.97,
+                fps => -(fps as f64),
+            };
+            sample_rate as f64 / (fps * (division & 0xFF) as f64)
+        }
+    };
+
+    let mut tempo = samples_per_pulse(SMF_DEFAULT_TEMPO);
+    let mut time = 0.0;
+    let mut last_pulse = 0;
+
+    Ok(messages
+        .into_iter()
+        .filter_map(|(pulse, message)| {
+            time += (pulse - last_pulse) as f64 * tempo;
+            last_pulse = pulse;
+
+            match message {
+                TimedMessage::Tempo(new_tempo) => {
+                    tempo = samples_per_pulse(new_tempo);
+                    None
+                }
+                TimedMessage::Note(event) => Some(NoteEvent {
+                    tick: time.round() as Tick,
+                    ..event
+                }),
+            }
+        })
+        .collect())
+}
+
+fn read_track(
+    track: usize,
+    chunk: &[u8],
+    messages: &mut Vec<(u64, TimedMessage)>,
+) -> Result<(), SmfError> {
+    let mut reader = Reader::new(chunk);
+    let mut pulse: u64 = 0;
+    let mut running_status = None;
+
+    while !reader.is_empty() {
+        pulse += reader.vlq(track)? as u64;
+        let offset = reader.offset;
+
+        match reader.peek()? {
+            META_EVENT => {
+                reader.u8()?;
+                let kind = reader.u8()?;
+                let len = reader.vlq(track)? as usize;
+                let data = reader.take(len)?;
+
+                match kind {
+                    META_END_OF_TRACK => break,
+                    META_TEMPO if len == 3 => messages.push((
+                        pulse,
+                        TimedMessage::Tempo(u32::from_be_bytes([0, data[0], data[1], data[2]])),
+                    )),
+                    _ => {}
+                }
+            }
+            // SysEx and escape sequences
+            0xF0 | 0xF7 => {
+                reader.u8()?;
+                let len = reader.vlq(track)? as usize;
+                reader.take(len)?;
+            }
+            status => {
+                let status = if status & 0x80 != 0 {
+                    reader.u8()?;
+                    running_status = Some(status);
+                    status
+                } else {
+                    running_status.ok_or(SmfError::InvalidEvent { track, offset })?
+                };
+
+                let mut data = [0; 2];
+                data[..data_len(status)].copy_from_slice(reader.take(data_len(status))?);
+
+                if data.iter().any(|byte| byte & 0x80 != 0) || status >= 0xF0 {
+                    return Err(SmfError::InvalidEvent { track, offset });
+                }
+
+                let event = match channel_message(status, data) {
+                    Some(MidiEvent::NoteOn {
+                        channel,
+                        note,
+                        velocity,
+                    }) => NoteEvent::on(0, note, velocity).with_channel(channel),
+                    Some(MidiEvent::NoteOff {
+                        channel,
+                        note,
+                        velocity,
+                    }) => NoteEvent::off(0, note, velocity).with_channel(channel),
+                    _ => continue,
+                };
+
+                messages.push((pulse, TimedMessage::Note(event)));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_smf, SmfError};
+    use crate::{daw::render::NoteEvent, midi::note::Note, param::f32::UnitInterval};
+    use alloc::vec::Vec;
+
+    #[test]
+    fn tempo_map() {
+        let track = |events: &[u8]| {
+            let mut chunk = Vec::from(*b"MTrk");
+            chunk.extend((events.len() as u32).to_be_bytes());
+            chunk.extend_from_slice(events);
+            chunk
+        };
+
+        // Type 1, 2 tracks, 96 pulses per quarter note
+        let mut file = Vec::from(*b"MThd\0\0\0\x06\0\x01\0\x02\0\x60");
+        file.extend(track(&[
+            // 60 BPM after the first quarter note
+            0x60, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40, //
+            0x00, 0xFF, 0x2F, 0x00,
+        ]));
+        file.extend(track(&[
+            // Note on C4 on channel 2, note off by zero velocity with running status
+            0x00, 0x92, 60, 127, //
+            0x81, 0x40, 60, 0, //
+            0x00, 0xFF, 0x2F, 0x00,
+        ]));
+
+        // A quarter note at 120 BPM, then a quarter note at 60 BPM
+        assert_eq!(
+            read_smf(&file, 48_000),
+            Ok(Vec::from([
+                NoteEvent::on(0, Note::C4, UnitInterval::MAX).with_channel(2),
+                NoteEvent::off(72_000, Note::C4, UnitInterval::MIN).with_channel(2),
+            ]))
+        );
+
+        // Delta time longer than 4 bytes
+        let mut file = Vec::from(*b"MThd\0\0\0\x06\0\x00\0\x01\0\x60");
+        file.extend(track(&[0x81, 0x80, 0x80, 0x80, 0x00, 0xFF, 0x2F, 0x00]));
+        assert_eq!(
+            read_smf(&file, 48_000),
+            Err(SmfError::InvalidEvent {
+                track: 0,
+                offset: 0
+            })
+        );
+
+        // Chunk length past the end of the file
+        let mut file = Vec::from(*b"MThd\0\0\0\x06\0\x00\0\x01\0\x60MTrk\xFF\xFF\xFF\xFF");
+        file.extend([0x00, 0xFF, 0x2F, 0x00]);
+        assert_eq!(read_smf(&file, 48_000), Err(SmfError::UnexpectedEnd));
+
+        // Zero pulses per quarter note, unknown SMPTE frame rate and zero pulses per frame
+        for division in [0x0000u16, 0xEC28, 0xE200] {
+            let mut file = Vec::from(*b"MThd\0\0\0\x06\0\x00\0\x01");
+            file.extend(division.to_be_bytes());
+            file.extend(track(&[0x00, 0xFF, 0x2F, 0x00]));
+            assert_eq!(
+                read_smf(&file, 48_000),
+                Err(SmfError::InvalidDivision(division))
+            );
+        }
+    }
+}
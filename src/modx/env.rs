@@ -1,4 +1,3 @@
-use super::mod_pack::ModTarget;
 use crate::{
     midi::event::MidiEventListener, osc::clock::Clock, param::f32::UnitInterval,
     sample::time::SampleCount,
@@ -17,7 +16,6 @@ pub struct EnvProps {
     pub index: usize,
     pub enabled: bool,
     pub amount: UnitInterval,
-    pub target: ModTarget,
 
     // Stages //
     pub delay: SampleCount,
@@ -91,7 +89,6 @@ impl EnvProps {
             index,
             enabled: false,
             amount: UnitInterval::MAX,
-            target: Default::default(),
             delay: SampleCount::zero(),
             attack: SampleCount::from_millis(1, sample_rate),
             hold: SampleCount::zero(),
@@ -214,9 +211,15 @@ impl Env {
                     Some(params.sustain)
                 }
             }
-            EnvState::NoteOff { at_tick } => params
-                .after_sustain(clock.tick - at_tick)
-                .map(UnitInterval::new),
+            EnvState::NoteOff { at_tick } => {
+                let after_sustain = params.after_sustain(clock.tick - at_tick);
+
+                if after_sustain.is_none() {
+                    self.state = EnvState::Idle;
+                }
+
+                after_sustain.map(UnitInterval::new)
+            }
         }
     }
 }
@@ -249,25 +252,15 @@ impl<const SIZE: usize> EnvPack<SIZE> {
         }
     }
 
+    /// Tick all envelopes, disabled or finished ones give `None`
     #[inline]
-    pub fn tick(
-        &mut self,
-        clock: &Clock,
-        target: ModTarget,
-        params: &[EnvProps],
-    ) -> Option<UnitInterval> {
-        // debug_assert_eq!(params.len(), self.envs.len());
-
-        params
-            .iter()
-            .zip(self.envs.iter_mut())
-            .filter_map(|(params, env)| {
-                if params.target == target {
-                    env.tick(clock, params)
-                } else {
-                    None
-                }
-            })
-            .next()
+    pub fn tick(&mut self, clock: &Clock, params: &[EnvProps]) -> [Option<UnitInterval>; SIZE] {
+        debug_assert_eq!(params.len(), self.envs.len());
+
+        let mut envs = self.envs.iter_mut().zip(params);
+        core::array::from_fn(|_| {
+            envs.next()
+                .and_then(|(env, params)| env.tick(clock, params))
+        })
     }
 }
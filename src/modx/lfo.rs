@@ -1,4 +1,3 @@
-use super::mod_pack::ModTarget;
 use crate::{
     midi::event::MidiEventListener,
     osc::clock::{Clock, Freq, Tick},
@@ -45,17 +44,39 @@ impl LfoWaveform {
         ]
         .into_iter()
     }
+
+    // TODO: Use LUT?
+    /// Waveform value in range [-1.0; 1.0] at given phase in range [0.0; 1.0)
+    #[inline]
+    pub fn at(&self, phase: f32) -> f32 {
+        match self {
+            LfoWaveform::Pulse(pulse_width) => {
+                if phase < pulse_width.inner() {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoWaveform::Sine => F32Ext::sin(phase * core::f32::consts::TAU),
+            LfoWaveform::Triangle => 4.0 * (phase + 0.25 - F32Ext::floor(phase + 0.75)).abs() - 1.0,
+            // LfoWaveform::Triangle => 1.0 - 2.0 * (2.0 * (phase + 0.25) - 1.0).abs(),
+            LfoWaveform::Saw => (phase * 2.0) - 1.0,
+            LfoWaveform::ReverseSaw => 1.0 - (phase * 2.0),
+        }
+    }
 }
 
 // TODO: Sync
-// pub enum LfoTrigger {
-//     /// LFO restarts on each note
-//     Trigger,
-//     /// LFO acts like an envelope running once on each note
-//     Envelope,
-//     /// LFO does not retrigger and keeps running in a loop
-//     Loop,
-// }
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LfoTrigger {
+    /// LFO restarts on each note, separately for each voice
+    #[default]
+    Trigger,
+    // /// LFO acts like an envelope running once on each note
+    // Envelope,
+    /// LFO does not retrigger and keeps running in a loop, in phase for all voices
+    Loop,
+}
 
 // #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 // pub enum LfoTarget {
@@ -76,8 +97,7 @@ pub struct LfoProps {
     // TODO: Store sample length instead of frequency
     pub freq: Freq,
     pub waveform: LfoWaveform,
-    // TODO: Multiple targets?
-    pub target: ModTarget,
+    pub trigger: LfoTrigger,
 }
 
 #[cfg(feature = "egui")]
@@ -114,6 +134,11 @@ impl crate::param::ui::EguiComponent for LfoProps {
                 ui.add(pulse_width.widget().text("Pulse width"));
             }
 
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.trigger, LfoTrigger::Trigger, "Retrigger");
+                ui.radio_value(&mut self.trigger, LfoTrigger::Loop, "Free-running");
+            });
+
             // TODO
             // ui.select(
             //     "Target",
@@ -140,7 +165,7 @@ impl LfoProps {
             amount: UnitInterval::MAX,
             freq: Freq::HZ,
             waveform: LfoWaveform::default(),
-            target: ModTarget::default(),
+            trigger: LfoTrigger::default(),
         }
     }
 
@@ -164,11 +189,10 @@ pub struct Lfo {
 impl MidiEventListener for Lfo {
     #[inline]
     fn note_on(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
-        let _ = clock;
         let _ = velocity;
         let _ = note;
         // self.phase = 0.0;
-        self.last_cycle = 0;
+        self.last_cycle = clock.tick;
         self.active = true;
     }
 
@@ -198,23 +222,9 @@ impl Lfo {
     //     }
     // }
 
-    // TODO: Use LUT?
     #[inline]
     pub fn at(phase: f32, params: &LfoProps) -> f32 {
-        match params.waveform {
-            LfoWaveform::Pulse(pulse_width) => {
-                if phase < pulse_width.inner() {
-                    1.0
-                } else {
-                    -1.0
-                }
-            }
-            LfoWaveform::Sine => F32Ext::sin(phase * core::f32::consts::TAU),
-            LfoWaveform::Triangle => 4.0 * (phase + 0.25 - F32Ext::floor(phase + 0.75)).abs() - 1.0,
-            // LfoWaveform::Triangle => 1.0 - 2.0 * (2.0 * (phase + 0.25) - 1.0).abs(),
-            LfoWaveform::Saw => (phase * 2.0) - 1.0,
-            LfoWaveform::ReverseSaw => 1.0 - (phase * 2.0),
-        }
+        params.waveform.at(phase)
     }
 
     pub fn tick(&mut self, clock: &Clock, params: &LfoProps) -> Option<SignedUnitInterval> {
@@ -222,12 +232,23 @@ impl Lfo {
             return None;
         }
 
-        let phase = clock.phase(params.freq, &mut self.last_cycle);
+        let phase = match params.trigger {
+            LfoTrigger::Trigger => {
+                let phase = clock.phase(params.freq, &mut self.last_cycle);
 
-        // Continue one cycle of LFO even if it is not triggered to avoid clicking. So here we stop non-triggered LFO only when phase is zero, i.e. the cycle is complete
-        if !self.active && phase <= EPSILON {
-            return None;
-        }
+                // Continue one cycle of LFO even if it is not triggered to avoid clicking. So here we stop non-triggered LFO only when phase is zero, i.e. the cycle is complete
+                if !self.active && phase <= EPSILON {
+                    return None;
+                }
+
+                phase
+            }
+            // Phase only depends on the clock, so every voice runs the same cycle regardless of notes
+            LfoTrigger::Loop => {
+                (clock.tick as f64 * params.freq.inner() as f64 / clock.sample_rate as f64 % 1.0)
+                    as f32
+            }
+        };
 
         let value = Self::at(phase, params) * params.amount.inner();
         let value = SignedUnitInterval::new_checked(value);
@@ -264,35 +285,27 @@ impl<const SIZE: usize> LfoPack<SIZE> {
         }
     }
 
+    /// Tick all LFOs, disabled or stopped ones give `None`
     #[inline]
     pub fn tick(
         &mut self,
         clock: &Clock,
-        target: ModTarget,
         params: &[LfoProps],
-    ) -> Option<SignedUnitInterval> {
+    ) -> [Option<SignedUnitInterval>; SIZE] {
         debug_assert_eq!(params.len(), self.lfos.len());
 
-        // Find first LFO with specified target. There should be only one LFO with this target
-        // LFO does not tick (advance) unless the target matches. So tick can be called multiple times per cycle for each target individually
-        params
-            .iter()
-            .zip(self.lfos.iter_mut())
-            .filter_map(|(params, lfo)| {
-                if params.target == target {
-                    lfo.tick(clock, params)
-                } else {
-                    None
-                }
-            })
-            .next()
+        let mut lfos = self.lfos.iter_mut().zip(params);
+        core::array::from_fn(|_| {
+            lfos.next()
+                .and_then(|(lfo, params)| lfo.tick(clock, params))
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        modx::lfo::{Lfo, LfoProps, LfoWaveform},
+        modx::lfo::{Lfo, LfoProps, LfoTrigger, LfoWaveform},
         osc::clock::{Clock, Freq},
         param::f32::UnitInterval,
     };
@@ -313,7 +326,7 @@ mod tests {
                 amount: UnitInterval::MAX,
                 freq,
                 waveform,
-                target: crate::modx::mod_pack::ModTarget::GlobalLevel,
+                trigger: LfoTrigger::Trigger,
             };
             assert_eq!(
                 lfo.tick(&clock.with_tick(0), &props),
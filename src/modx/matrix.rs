@@ -0,0 +1,395 @@
+use super::mod_pack::ModTarget;
+use crate::param::f32::{SignedUnitInterval, UnitInterval};
+use core::fmt::Display;
+
+/// Count of routes a [`ModMatrix`] can hold
+pub const MOD_MATRIX_SLOTS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModSource {
+    /// Bipolar LFO output
+    Lfo(usize),
+    /// Unipolar envelope output
+    Env(usize),
+    /// Velocity of the note played by the voice
+    Velocity,
+    /// Modulation wheel position, shared by all voices
+    ModWheel,
+}
+
+impl Display for ModSource {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ModSource::Lfo(lfo) => write!(f, "LFO{lfo}"),
+            ModSource::Env(env) => write!(f, "Env{env}"),
+            ModSource::Velocity => write!(f, "Velocity"),
+            ModSource::ModWheel => write!(f, "Mod wheel"),
+        }
+    }
+}
+
+impl ModSource {
+    #[inline]
+    pub fn each<const LFOS: usize, const ENVS: usize>() -> impl Iterator<Item = Self> {
+        (0..LFOS)
+            .map(Self::Lfo)
+            .chain((0..ENVS).map(Self::Env))
+            .chain([Self::Velocity, Self::ModWheel])
+    }
+}
+
+/// Values of all modulation sources of a voice at a single tick
+#[derive(Debug, Clone, Copy)]
+pub struct ModSources<const LFOS: usize, const ENVS: usize> {
+    pub lfos: [Option<SignedUnitInterval>; LFOS],
+    pub envs: [Option<UnitInterval>; ENVS],
+    pub velocity: UnitInterval,
+    pub mod_wheel: UnitInterval,
+}
+
+impl<const LFOS: usize, const ENVS: usize> ModSources<LFOS, ENVS> {
+    /// Source value, `None` if the source is disabled or not running
+    #[inline]
+    pub fn get(&self, source: ModSource) -> Option<f32> {
+        match source {
+            ModSource::Lfo(lfo) => self.lfos.get(lfo).copied().flatten().map(|lfo| lfo.inner()),
+            ModSource::Env(env) => self.envs.get(env).copied().flatten().map(|env| env.inner()),
+            ModSource::Velocity => Some(self.velocity.inner()),
+            ModSource::ModWheel => Some(self.mod_wheel.inner()),
+        }
+    }
+}
+
+/// Single connection of a modulation source to a target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModRoute {
+    pub source: ModSource,
+    /// Source scaling the depth of the route, e.g. velocity to make LFO deeper on harder notes
+    pub via: Option<ModSource>,
+    pub target: ModTarget,
+    pub depth: SignedUnitInterval,
+}
+
+impl ModRoute {
+    pub fn new(source: ModSource, target: ModTarget, depth: SignedUnitInterval) -> Self {
+        Self {
+            source,
+            via: None,
+            target,
+            depth,
+        }
+    }
+
+    pub fn with_via(self, via: ModSource) -> Self {
+        Self {
+            via: Some(via),
+            ..self
+        }
+    }
+
+    /// Contribution of the route, `None` if the source is not running. Via-source which is not running closes the route.
+    #[inline]
+    pub fn value<const LFOS: usize, const ENVS: usize>(
+        &self,
+        sources: &ModSources<LFOS, ENVS>,
+    ) -> Option<f32> {
+        let via = self
+            .via
+            .map(|via| sources.get(via).unwrap_or(0.0))
+            .unwrap_or(1.0);
+
+        sources
+            .get(self.source)
+            .map(|value| value * self.depth.inner() * via)
+    }
+
+    /// Contribution of the route as a multiplier, going from `1.0` at zero depth down to the unipolar source value at full depth. Bipolar LFO is remapped into unipolar, negative depth inverts the source.
+    #[inline]
+    pub fn gain<const LFOS: usize, const ENVS: usize>(
+        &self,
+        sources: &ModSources<LFOS, ENVS>,
+    ) -> Option<f32> {
+        let via = self
+            .via
+            .map(|via| sources.get(via).unwrap_or(0.0))
+            .unwrap_or(1.0);
+
+        sources.get(self.source).map(|value| {
+            let value = if let ModSource::Lfo(_) = self.source {
+                (value + 1.0) * 0.5
+            } else {
+                value
+            };
+            let value = if self.depth.inner() < 0.0 {
+                1.0 - value
+            } else {
+                value
+            };
+
+            1.0 - self.depth.inner().abs() * via * (1.0 - value)
+        })
+    }
+}
+
+/// Routes modulation sources to targets. Contributions of all routes to the same target are summed.
+#[derive(Debug, Clone)]
+pub struct ModMatrix {
+    slots: [Option<ModRoute>; MOD_MATRIX_SLOTS],
+}
+
+impl Default for ModMatrix {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModMatrix {
+    pub const fn new() -> Self {
+        Self {
+            slots: [None; MOD_MATRIX_SLOTS],
+        }
+    }
+
+    /// Put route into the first free slot, returning the route back if all slots are taken
+    pub fn push(&mut self, route: ModRoute) -> Result<usize, ModRoute> {
+        if let Some((index, slot)) = self
+            .slots
+            .iter_mut()
+            .enumerate()
+            .find(|(_, slot)| slot.is_none())
+        {
+            *slot = Some(route);
+            Ok(index)
+        } else {
+            Err(route)
+        }
+    }
+
+    #[inline]
+    pub fn remove(&mut self, slot: usize) -> Option<ModRoute> {
+        self.slots.get_mut(slot).and_then(Option::take)
+    }
+
+    #[inline]
+    pub fn slot_mut(&mut self, slot: usize) -> Option<&mut ModRoute> {
+        self.slots.get_mut(slot).and_then(Option::as_mut)
+    }
+
+    #[inline]
+    pub fn routes(&self) -> impl Iterator<Item = &ModRoute> {
+        self.slots.iter().flatten()
+    }
+
+    /// Summed modulation of the target, `None` if no route to the target has a running source
+    #[inline]
+    pub fn modulation<const LFOS: usize, const ENVS: usize>(
+        &self,
+        target: ModTarget,
+        sources: &ModSources<LFOS, ENVS>,
+    ) -> Option<f32> {
+        self.routes()
+            .filter(|route| route.target == target)
+            .filter_map(|route| route.value(sources))
+            .reduce(|sum, value| sum + value)
+    }
+
+    /// Level of a voice. Envelope routes to [`ModTarget::GlobalLevel`] define the level as their attack goes to velocity, without them (or when they are over) the voice is gated at velocity. Other level routes scale it by their [`ModRoute::gain`].
+    #[inline]
+    pub fn level<const LFOS: usize, const ENVS: usize>(
+        &self,
+        sources: &ModSources<LFOS, ENVS>,
+    ) -> UnitInterval {
+        let level_routes = || {
+            self.routes()
+                .filter(|route| route.target == ModTarget::GlobalLevel)
+        };
+
+        let level = level_routes()
+            .filter(|route| matches!(route.source, ModSource::Env(_)))
+            .filter_map(|route| route.value(sources))
+            .reduce(|sum, value| sum + value)
+            .map(UnitInterval::new)
+            .unwrap_or(sources.velocity);
+
+        level_routes()
+            .filter(|route| !matches!(route.source, ModSource::Env(_)))
+            .filter_map(|route| route.gain(sources))
+            .fold(level, |level, gain| level * UnitInterval::new(gain))
+    }
+
+    #[cfg(feature = "egui")]
+    pub fn egui<const LFOS: usize, const ENVS: usize, const OSCS: usize>(
+        &mut self,
+        ui: &mut egui::Ui,
+    ) {
+        ui.vertical(|ui| {
+            ui.label("Mod matrix");
+
+            egui::Grid::new("mod_matrix").show(ui, |ui| {
+                ui.label("Source");
+                ui.label("Via");
+                ui.label("Target");
+                ui.label("Depth");
+                ui.end_row();
+
+                self.slots.iter_mut().enumerate().for_each(|(index, slot)| {
+                    let Some(route) = slot else {
+                        return;
+                    };
+
+                    egui::ComboBox::from_id_source(("mod_source", index))
+                        .selected_text(format!("{}", route.source))
+                        .show_ui(ui, |ui| {
+                            ModSource::each::<LFOS, ENVS>().for_each(|source| {
+                                ui.selectable_value(&mut route.source, source, format!("{source}"));
+                            });
+                        });
+
+                    egui::ComboBox::from_id_source(("mod_via", index))
+                        .selected_text(
+                            route
+                                .via
+                                .map(|via| format!("{via}"))
+                                .unwrap_or_else(|| "None".into()),
+                        )
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut route.via, None, "None");
+                            ModSource::each::<LFOS, ENVS>().for_each(|via| {
+                                ui.selectable_value(&mut route.via, Some(via), format!("{via}"));
+                            });
+                        });
+
+                    egui::ComboBox::from_id_source(("mod_target", index))
+                        .selected_text(format!("{}", route.target))
+                        .show_ui(ui, |ui| {
+                            ModTarget::each::<OSCS>().for_each(|target| {
+                                ui.selectable_value(&mut route.target, target, format!("{target}"));
+                            });
+                        });
+
+                    ui.add(route.depth.widget());
+
+                    if ui.button("Remove").clicked() {
+                        *slot = None;
+                    }
+
+                    ui.end_row();
+                });
+            });
+
+            if ui.button("Add route").clicked() {
+                let _ = self.push(ModRoute::new(
+                    ModSource::each::<LFOS, ENVS>()
+                        .next()
+                        .unwrap_or(ModSource::Velocity),
+                    ModTarget::default(),
+                    SignedUnitInterval::EQUILIBRIUM,
+                ));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ModMatrix, ModRoute, ModSource, ModSources};
+    use crate::{
+        modx::mod_pack::ModTarget,
+        param::f32::{SignedUnitInterval, UnitInterval},
+    };
+
+    #[test]
+    fn level_routes() {
+        let mut sources = ModSources::<1, 1> {
+            lfos: [Some(SignedUnitInterval::new(-1.0))],
+            envs: [Some(UnitInterval::new(0.8))],
+            velocity: UnitInterval::new(0.5),
+            mod_wheel: UnitInterval::MIN,
+        };
+
+        let mut matrix = ModMatrix::new();
+        assert_eq!(matrix.level(&sources), UnitInterval::new(0.5));
+
+        // Half depth tremolo keeps velocity scaling and does not clip negative LFO half
+        matrix
+            .push(ModRoute::new(
+                ModSource::Lfo(0),
+                ModTarget::GlobalLevel,
+                SignedUnitInterval::new(0.5),
+            ))
+            .unwrap();
+        assert_eq!(matrix.level(&sources), UnitInterval::new(0.25));
+        sources.lfos = [Some(SignedUnitInterval::new(0.0))];
+        assert_eq!(matrix.level(&sources), UnitInterval::new(0.375));
+
+        // Envelope sets the level, tremolo scales it
+        matrix
+            .push(ModRoute::new(
+                ModSource::Env(0),
+                ModTarget::GlobalLevel,
+                SignedUnitInterval::MAX,
+            ))
+            .unwrap();
+        assert_eq!(matrix.level(&sources), UnitInterval::new(0.6));
+        sources.envs = [None];
+        assert_eq!(matrix.level(&sources), UnitInterval::new(0.375));
+    }
+
+    #[test]
+    fn summed_routes() {
+        let sources = ModSources::<2, 1> {
+            lfos: [
+                Some(SignedUnitInterval::new(0.5)),
+                Some(SignedUnitInterval::new(-0.25)),
+            ],
+            envs: [None],
+            velocity: UnitInterval::new(0.5),
+            mod_wheel: UnitInterval::MIN,
+        };
+
+        let mut matrix = ModMatrix::new();
+        assert_eq!(matrix.modulation(ModTarget::GlobalPitch, &sources), None);
+
+        matrix
+            .push(ModRoute::new(
+                ModSource::Lfo(0),
+                ModTarget::GlobalPitch,
+                SignedUnitInterval::MAX,
+            ))
+            .unwrap();
+        matrix
+            .push(ModRoute::new(
+                ModSource::Lfo(1),
+                ModTarget::GlobalPitch,
+                SignedUnitInterval::MIN,
+            ))
+            .unwrap();
+        // Finished envelope does not contribute
+        matrix
+            .push(ModRoute::new(
+                ModSource::Env(0),
+                ModTarget::GlobalPitch,
+                SignedUnitInterval::MAX,
+            ))
+            .unwrap();
+        assert_eq!(
+            matrix.modulation(ModTarget::GlobalPitch, &sources),
+            Some(0.75)
+        );
+
+        matrix
+            .push(
+                ModRoute::new(
+                    ModSource::Lfo(0),
+                    ModTarget::GlobalLevel,
+                    SignedUnitInterval::MAX,
+                )
+                .with_via(ModSource::Velocity),
+            )
+            .unwrap();
+        assert_eq!(
+            matrix.modulation(ModTarget::GlobalLevel, &sources),
+            Some(0.25)
+        );
+    }
+}
@@ -3,15 +3,9 @@ use core::fmt::Display;
 use super::{
     env::{EnvPack, EnvProps},
     lfo::{LfoPack, LfoProps},
-    ModValue,
+    matrix::ModSources,
 };
-use crate::{midi::event::MidiEventListener, osc::clock::Clock};
-
-// #[derive(Debug, Clone, Copy)]
-// pub enum ModSource {
-//     Lfo(usize),
-//     Env(usize),
-// }
+use crate::{midi::event::MidiEventListener, osc::clock::Clock, param::f32::UnitInterval};
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum ModTarget {
@@ -22,6 +16,16 @@ pub enum ModTarget {
 
     // Wavetable modulations //
     OscWtPos(usize),
+
+    // Virtual analog modulations //
+    OscPulseWidth(usize),
+
+    // Operator modulations //
+    OpPmIndex(usize),
+
+    // Voice filter modulations //
+    FilterCutoff,
+    FilterResonance,
 }
 
 impl Display for ModTarget {
@@ -32,6 +36,10 @@ impl Display for ModTarget {
             // ModTarget::OscPitch(osc) => write!(f, "OSC{osc} pitch"),
             // ModTarget::OscLevel(osc) => write!(f, "OSC{osc} level"),
             ModTarget::OscWtPos(osc) => write!(f, "OSC{osc} WT position"),
+            ModTarget::OscPulseWidth(osc) => write!(f, "OSC{osc} pulse width"),
+            ModTarget::OpPmIndex(osc) => write!(f, "OSC{osc} PM index"),
+            ModTarget::FilterCutoff => write!(f, "Filter cutoff"),
+            ModTarget::FilterResonance => write!(f, "Filter resonance"),
         }
     }
 }
@@ -44,6 +52,9 @@ impl ModTarget {
             // .chain((0..OSCS).map(|osc| Self::OscPitch(osc)))
             // .chain((0..OSCS).map(|osc| Self::OscLevel(osc)))
             .chain((0..OSCS).map(|osc| Self::OscWtPos(osc)))
+            .chain((0..OSCS).map(Self::OscPulseWidth))
+            .chain((0..OSCS).map(Self::OpPmIndex))
+            .chain([Self::FilterCutoff, Self::FilterResonance])
     }
 }
 
@@ -51,6 +62,8 @@ impl ModTarget {
 pub struct ModPack<const LFOS: usize, const ENVS: usize, const OSCS: usize> {
     lfos: LfoPack<LFOS>,
     envs: EnvPack<ENVS>,
+    /// Velocity of the last triggered note
+    velocity: UnitInterval,
 }
 
 impl<const LFOS: usize, const ENVS: usize, const OSCS: usize> MidiEventListener
@@ -63,6 +76,7 @@ impl<const LFOS: usize, const ENVS: usize, const OSCS: usize> MidiEventListener
         note: crate::midi::note::Note,
         velocity: crate::param::f32::UnitInterval,
     ) {
+        self.velocity = velocity;
         self.lfos.note_on(clock, note, velocity);
         self.envs.note_on(clock, note, velocity);
     }
@@ -84,23 +98,24 @@ impl<const LFOS: usize, const ENVS: usize, const OSCS: usize> ModPack<LFOS, ENVS
         Self {
             lfos: LfoPack::new(),
             envs: EnvPack::new(),
+            velocity: UnitInterval::MIN,
         }
     }
 
+    /// Tick all sources, giving their values to be routed by [`super::matrix::ModMatrix`]
+    #[inline]
     pub fn tick(
         &mut self,
         clock: &Clock,
-        target: ModTarget,
         lfo_props: &[LfoProps],
         env_props: &[EnvProps],
-    ) -> Option<ModValue> {
-        self.lfos
-            .tick(clock, target, lfo_props)
-            .map(|lfo_mod| ModValue::Lfo(lfo_mod))
-            .or_else(|| {
-                self.envs
-                    .tick(clock, target, env_props)
-                    .map(|env_mod| ModValue::Env(env_mod))
-            })
+        mod_wheel: UnitInterval,
+    ) -> ModSources<LFOS, ENVS> {
+        ModSources {
+            lfos: self.lfos.tick(clock, lfo_props),
+            envs: self.envs.tick(clock, env_props),
+            velocity: self.velocity,
+            mod_wheel,
+        }
     }
 }
@@ -22,7 +22,7 @@ impl Clock {
 
     #[inline(always)]
     pub fn tick(&mut self) {
-        self.tick += 1;
+        self.tick = self.tick.wrapping_add(1);
     }
 
     // #[inline(always)]
@@ -34,13 +34,13 @@ impl Clock {
     #[inline(always)]
     pub fn for_buffer(self, buffer_len: usize) -> impl Iterator<Item = Self> {
         let buffer_len = buffer_len as Tick;
-        (0..buffer_len).map(move |offset| self.with_tick(self.tick + offset))
+        (0..buffer_len).map(move |offset| self.with_tick(self.tick.wrapping_add(offset)))
     }
 
     /// Advances counter by buffer size. Must only be called when the whole buffer is processed by the system (in DAW).
     #[inline(always)]
     pub fn tick_for_buffer(&mut self, buffer_len: Tick) {
-        self.tick += buffer_len;
+        self.tick = self.tick.wrapping_add(buffer_len);
     }
 
     #[inline(always)]
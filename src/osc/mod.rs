@@ -1,25 +1,124 @@
 use crate::{
     midi::event::MidiEventListener,
-    modx::{am, fm, rm, ModValue, Modulate},
+    modx::{am, fm, mod_pack::ModTarget, rm, Modulate},
     param::f32::UnitInterval,
 };
 use clock::{Clock, Freq, Tick};
+use core::{
+    f32::consts::{PI, TAU},
+    fmt::Display,
+};
+#[allow(unused)]
+use num_traits::Float as _;
 
 pub mod clock;
+pub mod virtual_analog;
 
 pub trait Osc: Sized + Default + Send {
     type Props<'a>: Copy + Modulate + Send;
 
-    fn tick<'a>(&mut self, phase: f32, params: &Self::Props<'a>) -> f32;
+    /// Output at `phase` advancing by `phase_step` per sample, band-limited oscillators use the step to suppress aliasing
+    fn tick<'a>(&mut self, phase: f32, phase_step: f32, params: &Self::Props<'a>) -> f32;
 }
 
+/// The largest phase modulation index of the operator output in radians
+pub const OP_PM_INDEX_MAX: f32 = 4.0 * PI;
+
+/// Phase modulation index of the full feedback
+pub const OP_FEEDBACK_INDEX_MAX: f32 = PI;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OscOutput {
     Direct,
     FMNext,
     AMNext,
     RMNext,
-    // TODO: PM? Phase modulation
+    /// Linear phase modulation of the later operators set in the mask, bit `n` is the operator `n`
+    PM(u32),
+}
+
+impl OscOutput {
+    /// Phase modulation of the later operators in `targets`
+    #[inline]
+    pub fn pm(targets: impl IntoIterator<Item = usize>) -> Self {
+        Self::PM(targets.into_iter().fold(0, |mask, target| {
+            mask | 1u32.checked_shl(target as u32).unwrap_or(0)
+        }))
+    }
+
+    #[inline]
+    pub fn modulates(&self, target: usize) -> bool {
+        match self {
+            Self::PM(mask) => mask
+                .checked_shr(target as u32)
+                .is_some_and(|mask| mask & 1 == 1),
+            _ => false,
+        }
+    }
+}
+
+/// Operator graphs of phase modulation. Operators only modulate the later ones, thus the last operator is always a carrier.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OpAlgorithm {
+    /// Every operator modulates the next one, the last is the only carrier
+    #[default]
+    Stack,
+    /// Even operators modulate the following odd carriers
+    Pairs,
+    /// All operators modulate the last one
+    Branch,
+    /// The first operator modulates all the other carriers
+    Fan,
+    /// All operators are carriers
+    Parallel,
+}
+
+impl Display for OpAlgorithm {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            OpAlgorithm::Stack => write!(f, "Stack"),
+            OpAlgorithm::Pairs => write!(f, "Pairs"),
+            OpAlgorithm::Branch => write!(f, "Branch"),
+            OpAlgorithm::Fan => write!(f, "Fan"),
+            OpAlgorithm::Parallel => write!(f, "Parallel"),
+        }
+    }
+}
+
+impl OpAlgorithm {
+    #[inline]
+    pub fn each() -> impl Iterator<Item = Self> {
+        [
+            Self::Stack,
+            Self::Pairs,
+            Self::Branch,
+            Self::Fan,
+            Self::Parallel,
+        ]
+        .into_iter()
+    }
+
+    /// Routing of the operator `index` out of `oscs` operators
+    pub fn output(self, index: usize, oscs: usize) -> OscOutput {
+        let last = oscs.saturating_sub(1);
+
+        match self {
+            _ if index >= last => OscOutput::Direct,
+            OpAlgorithm::Stack => OscOutput::pm([index + 1]),
+            OpAlgorithm::Pairs if index.is_multiple_of(2) => OscOutput::pm([index + 1]),
+            OpAlgorithm::Branch => OscOutput::pm([last]),
+            OpAlgorithm::Fan if index == 0 => OscOutput::pm(1..oscs),
+            OpAlgorithm::Pairs | OpAlgorithm::Fan | OpAlgorithm::Parallel => OscOutput::Direct,
+        }
+    }
+
+    /// Enable all operators routing them by the algorithm
+    pub fn apply<O: Osc, const OSCS: usize>(self, props: &mut [OpProps<'_, O, OSCS>]) {
+        props.iter_mut().for_each(|props| {
+            props.enabled = true;
+            props.output = self.output(props.index, OSCS);
+        });
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -37,6 +136,10 @@ pub struct OpProps<'a, O: Osc, const OSCS: usize> {
     enabled: bool,
     osc: O::Props<'a>,
     output: OscOutput,
+    /// Peak phase deviation in radians of the operators modulated by [`OscOutput::PM`]
+    pm_index: f32,
+    /// Phase modulation of the operator by its own output
+    feedback: UnitInterval,
     // TODO: Mix (better balanced between oscs)
     // TODO: Tuning
     tune_semitones: i8,
@@ -52,6 +155,8 @@ impl<'a, O: Osc, const OSCS: usize> Clone for OpProps<'a, O, OSCS> {
             enabled: self.enabled.clone(),
             osc: self.osc.clone(),
             output: self.output.clone(),
+            pm_index: self.pm_index,
+            feedback: self.feedback,
             tune_semitones: self.tune_semitones.clone(),
             tune_cents: self.tune_cents.clone(),
         }
@@ -87,8 +192,34 @@ impl<'a, O: Osc, const OSCS: usize> crate::param::ui::EguiComponent for OpProps<
                 OscOutput::RMNext,
                 format!("OSC{next_osc} RM"),
             );
+            if ui
+                .radio(matches!(self.output, OscOutput::PM(_)), "PM")
+                .clicked()
+            {
+                self.output = OscOutput::pm([next_osc]);
+            }
+
+            if let OscOutput::PM(mask) = &mut self.output {
+                ui.horizontal(|ui| {
+                    (next_osc..OSCS.min(u32::BITS as usize)).for_each(|target| {
+                        let mut modulates = *mask & 1 << target != 0;
+                        if ui
+                            .checkbox(&mut modulates, format!("OSC{target}"))
+                            .changed()
+                        {
+                            *mask ^= 1 << target;
+                        }
+                    });
+                });
+
+                ui.add(
+                    egui::Slider::new(&mut self.pm_index, 0.0..=OP_PM_INDEX_MAX).text("PM index"),
+                );
+            }
         }
 
+        ui.add(self.feedback.widget().text("Feedback"));
+
         ui.add(
             egui::Slider::from_get_set(-36.0..=36.0, |new_value| {
                 if let Some(new_value) = new_value {
@@ -117,10 +248,18 @@ impl<'a, O: Osc, const OSCS: usize> Modulate for OpProps<'a, O, OSCS> {
     #[inline]
     fn modulated(
         &self,
-        f: impl FnMut(crate::modx::mod_pack::ModTarget) -> Option<ModValue>,
+        mut f: impl FnMut(crate::modx::mod_pack::ModTarget) -> Option<f32>,
     ) -> Self {
+        let pm_index = match f(ModTarget::OpPmIndex(self.index)) {
+            Some(pm_index_mod) => {
+                (self.pm_index + pm_index_mod * OP_PM_INDEX_MAX).clamp(0.0, OP_PM_INDEX_MAX)
+            }
+            None => self.pm_index,
+        };
+
         Self {
             osc: self.osc.modulated(f),
+            pm_index,
             ..*self
         }
     }
@@ -133,6 +272,8 @@ impl<'a, O: Osc, const OSCS: usize> OpProps<'a, O, OSCS> {
             enabled: index == 0,
             osc,
             output: OscOutput::Direct,
+            pm_index: 1.0,
+            feedback: UnitInterval::MIN,
             tune_semitones: 0,
             tune_cents: 0,
         }
@@ -142,19 +283,55 @@ impl<'a, O: Osc, const OSCS: usize> OpProps<'a, O, OSCS> {
     pub fn kind_mut(&mut self) -> &mut O::Props<'a> {
         &mut self.osc
     }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    #[inline]
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    #[inline]
+    pub fn output(&self) -> OscOutput {
+        self.output
+    }
+
+    #[inline]
+    pub fn set_output(&mut self, output: OscOutput) {
+        self.output = output;
+    }
+
+    #[inline]
+    pub fn pm_index(&self) -> f32 {
+        self.pm_index
+    }
+
+    /// Set phase modulation index clamped to [`OP_PM_INDEX_MAX`]
+    #[inline]
+    pub fn set_pm_index(&mut self, pm_index: f32) {
+        self.pm_index = pm_index.clamp(0.0, OP_PM_INDEX_MAX);
+    }
+
+    #[inline]
+    pub fn feedback_mut(&mut self) -> &mut UnitInterval {
+        &mut self.feedback
+    }
 }
 
 #[derive(Clone)]
 pub struct OpParams<'a, O: Osc, const OSCS: usize> {
     pub props: OpProps<'a, O, OSCS>,
-    pub pitch_mod: Option<ModValue>,
+    /// Pitch modulation in octaves
+    pub pitch_mod: Option<f32>,
 }
 
 impl<'a, O: Osc, const OSCS: usize> OpParams<'a, O, OSCS> {
     #[inline]
     fn tune_mod(&self) -> f32 {
         self.pitch_mod
-            .map(|pitch_mod| pitch_mod.as_sui().inner())
             .unwrap_or(0.0)
             + self.props.tune_semitones as f32 / 12.0
             + self.props.tune_cents as f32 / 1200.0
@@ -168,6 +345,8 @@ pub struct OpState {
     last_freq: Freq,
     // min_phase_step: f32,
     phase_step: f32,
+    /// The last two outputs, feedback takes their average to not oscillate at Nyquist
+    last_outputs: [f32; 2],
 }
 
 impl OpState {
@@ -194,9 +373,10 @@ impl<O: Osc, const OSCS: usize> MidiEventListener for OperatorPack<O, OSCS> {
         let _ = clock;
         let _ = note;
         let _ = velocity;
-        self.states
-            .iter_mut()
-            .for_each(|state| state.last_cycle = 0);
+        self.states.iter_mut().for_each(|state| {
+            state.last_cycle = 0;
+            state.last_outputs = [0.0; 2];
+        });
     }
 
     #[inline]
@@ -216,6 +396,7 @@ impl<O: Osc + 'static, const OSCS: usize> OperatorPack<O, OSCS> {
                 last_cycle: 0,
                 last_freq: Freq::ZERO,
                 phase_step: 0.0,
+                last_outputs: [0.0; 2],
                 // freq: 0.0,
             }),
         }
@@ -223,13 +404,17 @@ impl<O: Osc + 'static, const OSCS: usize> OperatorPack<O, OSCS> {
 
     // Note: Don't inline
     pub fn tick<'a>(&mut self, clock: &Clock, freq: Freq, params: &[OpParams<'a, O, OSCS>]) -> f32 {
+        // Phase offsets in radians of the operators modulated by the previous ones
+        let mut pm = [0.0; OSCS];
+
         self.oscs
             .iter_mut()
             .zip(params)
             .zip(self.states.iter_mut())
+            .enumerate()
             .fold(
                 (OscMod::Direct(0.0), 0.0),
-                |(modulation, mix), ((osc, params), state)| {
+                |(modulation, mix), (index, ((osc, params), state))| {
                     if !params.props.enabled {
                         // TODO: Why so? Modulation from previous enabled oscillator should be passed?
                         return (OscMod::None, mix);
@@ -245,7 +430,18 @@ impl<O: Osc + 'static, const OSCS: usize> OperatorPack<O, OSCS> {
                     state.update(clock, freq);
                     let phase = clock.phase_fast(state.phase_step, &mut state.last_cycle);
 
-                    let output = osc.tick(phase, &params.props.osc);
+                    let [last_output, prev_output] = state.last_outputs;
+                    let feedback = params.props.feedback.inner()
+                        * OP_FEEDBACK_INDEX_MAX
+                        * (last_output + prev_output)
+                        / 2.0;
+                    let phase = phase + (pm[index] + feedback) / TAU;
+                    let phase = phase - phase.floor();
+                    // Tiny negative phases round up to a whole cycle
+                    let phase = if phase < 1.0 { phase } else { 0.0 };
+
+                    let output = osc.tick(phase, state.phase_step, &params.props.osc);
+                    state.last_outputs = [output, last_output];
 
                     let output = match modulation {
                         OscMod::AM(m) => am(output, m),
@@ -253,6 +449,14 @@ impl<O: Osc + 'static, const OSCS: usize> OperatorPack<O, OSCS> {
                         _ => output,
                     };
 
+                    if let OscOutput::PM(_) = params.props.output {
+                        pm.iter_mut()
+                            .enumerate()
+                            .skip(index + 1)
+                            .filter(|(target, _)| params.props.output.modulates(*target))
+                            .for_each(|(_, pm)| *pm += output * params.props.pm_index);
+                    }
+
                     let mix = if let OscOutput::Direct = params.props.output {
                         // Direct output mixes with other outputs
                         mix + output
@@ -267,6 +471,7 @@ impl<O: Osc + 'static, const OSCS: usize> OperatorPack<O, OSCS> {
                             OscOutput::FMNext => OscMod::FM(output),
                             OscOutput::AMNext => OscMod::AM(output),
                             OscOutput::RMNext => OscMod::RM(output),
+                            OscOutput::PM(_) => OscMod::None,
                         },
                         mix,
                     )
@@ -275,3 +480,138 @@ impl<O: Osc + 'static, const OSCS: usize> OperatorPack<O, OSCS> {
             .1
     }
 }
+
+/// Part of the spectral energy of `samples` which is not on the harmonics of a tone of `cycles` periods
+#[cfg(test)]
+pub(crate) fn aliasing(samples: &[f32], cycles: usize) -> f32 {
+    use core::f32::consts::TAU;
+    #[allow(unused)]
+    use num_traits::Float as _;
+
+    let len = samples.len();
+    let (aliased, total) = (1..len / 2).fold((0.0, 0.0), |(aliased, total), bin| {
+        let (re, im) = samples
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(re, im), (index, sample)| {
+                let angle = TAU * (bin * index % len) as f32 / len as f32;
+                (re + sample * angle.cos(), im - sample * angle.sin())
+            });
+        let energy = re * re + im * im;
+
+        if bin % cycles == 0 {
+            (aliased, total + energy)
+        } else {
+            (aliased + energy, total + energy)
+        }
+    });
+
+    aliased / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        clock::{Clock, Freq},
+        virtual_analog::{VaWaveform, VirtualAnalogOsc, VirtualAnalogProps},
+        OpAlgorithm, OpParams, OpProps, OperatorPack, OscOutput,
+    };
+    use crate::{modx::fm, param::f32::UnitInterval};
+    use core::f32::consts::TAU;
+    #[allow(unused)]
+    use num_traits::Float as _;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    fn params(
+        algorithm: OpAlgorithm,
+        pm_index: f32,
+    ) -> [OpParams<'static, VirtualAnalogOsc, 2>; 2] {
+        let mut props = core::array::from_fn(|index| {
+            let mut osc = VirtualAnalogProps::new(index);
+            osc.waveform = VaWaveform::Sine;
+
+            let mut props = OpProps::new(index, osc);
+            props.set_pm_index(pm_index);
+            props
+        });
+        algorithm.apply(&mut props);
+
+        props.map(|props| OpParams {
+            props,
+            pitch_mod: None,
+        })
+    }
+
+    #[test]
+    fn phase_modulation() {
+        assert_eq!(OpAlgorithm::Stack.output(0, 3), OscOutput::pm([1]));
+        assert_eq!(OpAlgorithm::Pairs.output(1, 4), OscOutput::Direct);
+        assert_eq!(OpAlgorithm::Branch.output(1, 4), OscOutput::pm([3]));
+        assert_eq!(OpAlgorithm::Fan.output(0, 4), OscOutput::pm(1..4));
+        assert!(OscOutput::pm(1..4).modulates(2));
+        assert!(!OscOutput::pm(1..4).modulates(0));
+
+        let freq = Freq::Hz(440);
+        let pm_index = 2.5;
+        let params = params(OpAlgorithm::Stack, pm_index);
+        assert_eq!(params[0].props.output(), OscOutput::pm([1]));
+        assert!(params[1].props.is_enabled());
+
+        // The carrier phase is offset by the modulator output, negative halves included
+        let mut clock = Clock::zero(SAMPLE_RATE);
+        let mut pack = OperatorPack::new(|_| VirtualAnalogOsc::default());
+        let mut last_cycle = 0;
+        (0..1_000).for_each(|_| {
+            let output = pack.tick(&clock, freq, &params);
+
+            let phase = clock.phase_fast(freq.inner() / SAMPLE_RATE as f32, &mut last_cycle);
+            let expected = (TAU * phase + pm_index * (TAU * phase).sin()).sin();
+            assert!((output - expected).abs() < 1e-3, "{output} vs {expected}");
+
+            clock.tick();
+        });
+
+        // Feedback turns the sine into a saw-like wave without leaving the range
+        let mut params = params;
+        params[0].props.set_enabled(false);
+        *params[1].props.feedback_mut() = UnitInterval::MAX;
+        let mut clock = Clock::zero(SAMPLE_RATE);
+        let mut pack = OperatorPack::new(|_| VirtualAnalogOsc::default());
+        let mut last_cycle = 0;
+        let deviation = (0..1_000).fold(0.0, |deviation: f32, _| {
+            let output = pack.tick(&clock, freq, &params);
+            assert!(output.abs() <= 1.0);
+
+            let phase = clock.phase_fast(freq.inner() / SAMPLE_RATE as f32, &mut last_cycle);
+            clock.tick();
+
+            deviation.max((output - (TAU * phase).sin()).abs())
+        });
+        assert!(deviation > 0.1, "{deviation}");
+    }
+
+    #[test]
+    fn pitch_mod_down() {
+        assert_eq!(fm(Freq::Hz(440), 1.0), Freq::Hz(880));
+        assert_eq!(fm(Freq::Hz(440), -1.0), Freq::Hz(220));
+
+        // Negative pitch modulation, e.g. pitch bend down, lowers the operator an octave
+        let mut params = params(OpAlgorithm::Parallel, 1.0);
+        params[1].props.set_enabled(false);
+        params[0].pitch_mod = Some(-1.0);
+
+        let mut clock = Clock::zero(SAMPLE_RATE);
+        let mut pack = OperatorPack::new(|_| VirtualAnalogOsc::default());
+        let mut last_cycle = 0;
+        (0..1_000).for_each(|_| {
+            let output = pack.tick(&clock, Freq::Hz(440), &params);
+
+            let phase = clock.phase_fast(220.0 / SAMPLE_RATE as f32, &mut last_cycle);
+            let expected = (TAU * phase).sin();
+            assert!((output - expected).abs() < 1e-3, "{output} vs {expected}");
+
+            clock.tick();
+        });
+    }
+}
@@ -0,0 +1,236 @@
+use super::Osc;
+use crate::{
+    modx::{mod_pack::ModTarget, Modulate},
+    param::f32::UnitInterval,
+    synth::Synth,
+};
+use core::{f32::consts::TAU, fmt::Display};
+#[allow(unused)]
+use num_traits::Float as _;
+
+/// Pulse width is kept off the edges so the pulse never disappears
+pub const VA_PULSE_WIDTH_MARGIN: f32 = 0.01;
+
+pub type VirtualAnalogSynth<
+    const VOICES: usize,
+    const LFOS: usize,
+    const ENVS: usize,
+    const OSCS: usize,
+> = Synth<VirtualAnalogOsc, VOICES, LFOS, ENVS, OSCS>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum VaWaveform {
+    #[default]
+    Saw,
+    /// Pulse wave of [`VirtualAnalogProps::pulse_width`]
+    Square,
+    Triangle,
+    Sine,
+}
+
+impl Display for VaWaveform {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            VaWaveform::Saw => write!(f, "Saw"),
+            VaWaveform::Square => write!(f, "Square"),
+            VaWaveform::Triangle => write!(f, "Triangle"),
+            VaWaveform::Sine => write!(f, "Sine"),
+        }
+    }
+}
+
+impl VaWaveform {
+    #[inline]
+    pub fn each() -> impl Iterator<Item = Self> {
+        [Self::Saw, Self::Square, Self::Triangle, Self::Sine].into_iter()
+    }
+}
+
+/// The properties of virtual analog oscillator. Global for all oscillator instances.
+#[derive(Debug, Clone, Copy)]
+pub struct VirtualAnalogProps {
+    osc_index: usize,
+    pub waveform: VaWaveform,
+    /// Part of the cycle the square is high, modulated by [`ModTarget::OscPulseWidth`]
+    pub pulse_width: UnitInterval,
+}
+
+impl Modulate for VirtualAnalogProps {
+    #[inline]
+    fn modulated(&self, mut f: impl FnMut(ModTarget) -> Option<f32>) -> Self {
+        if let Some(pulse_width_mod) = f(ModTarget::OscPulseWidth(self.osc_index)) {
+            Self {
+                pulse_width: UnitInterval::new(self.pulse_width.inner() + pulse_width_mod),
+                ..*self
+            }
+        } else {
+            *self
+        }
+    }
+}
+
+#[cfg(feature = "egui")]
+impl crate::param::ui::EguiComponent for VirtualAnalogProps {
+    fn egui(&mut self, ui: &mut egui::Ui, _params: crate::param::ui::DefaultUiParams) {
+        ui.vertical(|ui| {
+            // Naive shape with a small phase step
+            crate::param::ui::egui_wave(ui, |x| {
+                VirtualAnalogOsc::default().tick(x.min(0.999), 0.001, self) * 0.99
+            });
+
+            VaWaveform::each().for_each(|waveform| {
+                ui.radio_value(&mut self.waveform, waveform, format!("{waveform}"));
+            });
+
+            if self.waveform == VaWaveform::Square {
+                ui.add(self.pulse_width.widget().text("Pulse width"));
+            }
+        });
+    }
+}
+
+impl VirtualAnalogProps {
+    pub fn new(osc_index: usize) -> Self {
+        Self {
+            osc_index,
+            waveform: VaWaveform::default(),
+            pulse_width: UnitInterval::EQUILIBRIUM,
+        }
+    }
+}
+
+/// Band-limited step residual, smooths a unit step at phase 0 over a phase step on each side
+#[inline]
+fn poly_blep(phase: f32, phase_step: f32) -> f32 {
+    if phase < phase_step {
+        let x = phase / phase_step;
+        2.0 * x - x * x - 1.0
+    } else if phase > 1.0 - phase_step {
+        let x = (phase - 1.0) / phase_step;
+        x * x + 2.0 * x + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// Band-limited ramp residual, integral of [`poly_blep`] in phase step units. Smooths a unit change of slope at phase 0.
+#[inline]
+fn poly_blamp(phase: f32, phase_step: f32) -> f32 {
+    if phase < phase_step {
+        let x = phase / phase_step - 1.0;
+        -x * x * x / 3.0
+    } else if phase > 1.0 - phase_step {
+        let x = (phase - 1.0) / phase_step + 1.0;
+        x * x * x / 3.0
+    } else {
+        0.0
+    }
+}
+
+/// Wrap phase into 0.0..1.0
+#[inline]
+fn wrap(phase: f32) -> f32 {
+    phase - phase.floor()
+}
+
+/// Virtual analog oscillator with PolyBLEP saw and square and PolyBLAMP triangle
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VirtualAnalogOsc {}
+
+impl Osc for VirtualAnalogOsc {
+    type Props<'a> = VirtualAnalogProps;
+
+    #[inline]
+    fn tick<'a>(&mut self, phase: f32, phase_step: f32, params: &Self::Props<'a>) -> f32 {
+        // Corrections overlap at phase steps above a half of the cycle
+        let dt = phase_step.abs().min(0.5);
+
+        match params.waveform {
+            VaWaveform::Saw => 2.0 * phase - 1.0 - poly_blep(phase, dt),
+            VaWaveform::Square => {
+                let pulse_width = params
+                    .pulse_width
+                    .inner()
+                    .clamp(VA_PULSE_WIDTH_MARGIN, 1.0 - VA_PULSE_WIDTH_MARGIN);
+                let naive = if phase < pulse_width { 1.0 } else { -1.0 };
+
+                naive + poly_blep(phase, dt) - poly_blep(wrap(phase - pulse_width), dt)
+            }
+            VaWaveform::Triangle => {
+                // Slope changes by 8 per cycle at the bottom and the top corners
+                let naive = 1.0 - 4.0 * (phase - 0.5).abs();
+
+                naive + 8.0 * dt * (poly_blamp(phase, dt) - poly_blamp(wrap(phase - 0.5), dt))
+            }
+            VaWaveform::Sine => (TAU * phase).sin(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VaWaveform, VirtualAnalogOsc, VirtualAnalogProps};
+    use crate::{
+        modx::{mod_pack::ModTarget, Modulate},
+        osc::{aliasing, Osc},
+        param::f32::UnitInterval,
+    };
+    use alloc::vec::Vec;
+    #[allow(unused)]
+    use num_traits::Float as _;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+    const FREQ: f32 = 5_000.0;
+    /// 100 cycles, each harmonic falls exactly on a DFT bin
+    const LEN: usize = 960;
+
+    fn render(props: &VirtualAnalogProps, phase_step: f32) -> Vec<f32> {
+        let mut osc = VirtualAnalogOsc::default();
+        (0..LEN)
+            .map(|index| {
+                let phase = (index as f32 * FREQ / SAMPLE_RATE).fract();
+                osc.tick(phase, phase_step, props)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn band_limited_waveforms() {
+        let phase_step = FREQ / SAMPLE_RATE;
+        let cycles = (FREQ * LEN as f32 / SAMPLE_RATE) as usize;
+
+        [VaWaveform::Saw, VaWaveform::Square, VaWaveform::Triangle]
+            .into_iter()
+            .for_each(|waveform| {
+                let mut props = VirtualAnalogProps::new(0);
+                props.waveform = waveform;
+                props.pulse_width = UnitInterval::new(0.3);
+
+                // Zero phase step disables the corrections
+                let naive = aliasing(&render(&props, 0.0), cycles);
+                let band_limited = aliasing(&render(&props, phase_step), cycles);
+
+                assert!(
+                    band_limited < naive / 2.0,
+                    "{waveform}: {band_limited} vs {naive}"
+                );
+            });
+    }
+
+    #[test]
+    fn pulse_width_modulation() {
+        let mut props = VirtualAnalogProps::new(1);
+        props.waveform = VaWaveform::Square;
+
+        let modulated =
+            props.modulated(|target| (target == ModTarget::OscPulseWidth(1)).then_some(-0.25));
+        assert_eq!(modulated.pulse_width, UnitInterval::new(0.25));
+        assert_eq!(VirtualAnalogOsc::default().tick(0.3, 0.0, &modulated), -1.0);
+        assert_eq!(
+            props
+                .modulated(|target| (target == ModTarget::OscPulseWidth(0)).then_some(-0.25))
+                .pulse_width,
+            UnitInterval::EQUILIBRIUM
+        );
+    }
+}
@@ -3,6 +3,8 @@ use core::{
     iter::Sum,
     ops::{Add, Div, Mul, Neg, RangeInclusive, Sub},
 };
+#[allow(unused)]
+use num_traits::Float as _;
 
 // TODO: Rename mod to `interval`
 
@@ -254,13 +256,120 @@ impl SignedUnitInterval {
     pub fn remap_into_ui(&self) -> UnitInterval {
         UnitInterval::new((self.0 + 1.0) / 2.0)
     }
+
+    #[cfg(feature = "egui")]
+    pub fn widget(&mut self) -> egui::Slider<'_> {
+        egui::Slider::from_get_set(-1.0..=1.0, |new_value| {
+            if let Some(new_value) = new_value {
+                *self = SignedUnitInterval::new(new_value as f32);
+            }
+
+            self.inner() as f64
+        })
+    }
+}
+
+/// Level in decibels stored as a fader position in [`UnitInterval`]. Positions from [`DbLevel::SILENT_POSITION`] up map linearly to [`DbLevel::DB_MIN`]..=[`DbLevel::DB_MAX`], the bottom range below is silence.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct DbLevel(UnitInterval);
+
+impl DbLevel {
+    /// Level at [`DbLevel::SILENT_POSITION`], the lowest audible one
+    pub const DB_MIN: f32 = -60.0;
+    pub const DB_MAX: f32 = 6.0;
+    /// Positions below are silence
+    pub const SILENT_POSITION: f32 = 0.01;
+    pub const SILENT: Self = Self(UnitInterval::MIN);
+    /// 0 dB
+    pub const UNITY: Self = Self(UnitInterval(
+        Self::SILENT_POSITION
+            + (1.0 - Self::SILENT_POSITION) * -Self::DB_MIN / (Self::DB_MAX - Self::DB_MIN),
+    ));
+    pub const MAX: Self = Self(UnitInterval::MAX);
+
+    #[inline]
+    pub fn new(position: UnitInterval) -> Self {
+        Self(position)
+    }
+
+    /// Level closest to `db`, values below [`DbLevel::DB_MIN`] are silence
+    #[inline]
+    pub fn from_db(db: f32) -> Self {
+        if db < Self::DB_MIN {
+            Self::SILENT
+        } else {
+            Self(UnitInterval::new(
+                Self::SILENT_POSITION
+                    + (1.0 - Self::SILENT_POSITION) * (db - Self::DB_MIN)
+                        / (Self::DB_MAX - Self::DB_MIN),
+            ))
+        }
+    }
+
+    /// Fader position
+    #[inline]
+    pub fn position(&self) -> UnitInterval {
+        self.0
+    }
+
+    #[inline]
+    pub fn is_silent(&self) -> bool {
+        self.0.inner() < Self::SILENT_POSITION
+    }
+
+    /// Level in decibels, negative infinity in the silent range
+    #[inline]
+    pub fn db(&self) -> f32 {
+        if self.is_silent() {
+            f32::NEG_INFINITY
+        } else {
+            Self::DB_MIN
+                + (self.0.inner() - Self::SILENT_POSITION) / (1.0 - Self::SILENT_POSITION)
+                    * (Self::DB_MAX - Self::DB_MIN)
+        }
+    }
+
+    /// Linear amplitude factor
+    #[inline]
+    pub fn gain(&self) -> f32 {
+        if self.is_silent() {
+            0.0
+        } else {
+            10f32.powf(self.db() / 20.0)
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    pub fn widget(&mut self) -> egui::Slider<'_> {
+        egui::Slider::from_get_set(0.0..=1.0, |new_value| {
+            if let Some(new_value) = new_value {
+                *self = DbLevel::new(UnitInterval::new(new_value as f32));
+            }
+
+            self.0.inner() as f64
+        })
+        .custom_formatter(|position, _| {
+            let level = DbLevel::new(UnitInterval::new(position as f32));
+            if level.is_silent() {
+                "-inf dB".into()
+            } else {
+                format!("{:.1} dB", level.db())
+            }
+        })
+    }
+}
+
+impl Display for DbLevel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} dB", self.db())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::param::f32::SignedUnitInterval;
 
-    use super::UnitInterval;
+    use super::{DbLevel, UnitInterval};
 
     #[test]
     fn remapping() {
@@ -288,4 +397,23 @@ mod tests {
             UnitInterval::EQUILIBRIUM,
         );
     }
+
+    #[test]
+    fn db_level() {
+        assert_eq!(DbLevel::UNITY.gain(), 1.0);
+        assert_eq!(DbLevel::from_db(0.0), DbLevel::UNITY);
+        assert_eq!(DbLevel::SILENT.gain(), 0.0);
+        assert_eq!(DbLevel::SILENT.db(), f32::NEG_INFINITY);
+        assert_eq!(DbLevel::from_db(-100.0), DbLevel::SILENT);
+        assert_eq!(DbLevel::from_db(100.0), DbLevel::MAX);
+
+        // The lowest level is still audible, the bottom range is silent
+        let min = DbLevel::from_db(DbLevel::DB_MIN);
+        assert!((min.db() - DbLevel::DB_MIN).abs() < 1e-4, "{}", min.db());
+        assert!((min.gain() - 0.001).abs() < 1e-6);
+        assert_eq!(DbLevel::new(UnitInterval::new(0.005)).gain(), 0.0);
+
+        let half = DbLevel::from_db(-6.0).gain();
+        assert!((half - 0.5).abs() < 0.01, "{half}");
+    }
 }
@@ -1,9 +1,11 @@
 use super::Sample;
-use crate::param::f32::UnitInterval;
+use crate::param::f32::{SignedUnitInterval, UnitInterval};
 use core::{
     iter::Sum,
     ops::{Add, Div, Mul, Sub},
 };
+#[allow(unused)]
+use num_traits::Float as _;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Frame<T = f32, const SIZE: usize = 2> {
@@ -90,6 +92,17 @@ impl Frame<f32, 2> {
             ],
         }
     }
+
+    /// Constant-power panning normalized to unity gain in the center, hard panning boosts the side by 3 dB
+    #[inline]
+    pub fn panned(&self, pan: SignedUnitInterval) -> Self {
+        Self {
+            channels: [
+                self.channels[0] * (1.0 - pan.inner()).sqrt(),
+                self.channels[1] * (1.0 + pan.inner()).sqrt(),
+            ],
+        }
+    }
 }
 
 impl<T: Copy, const SIZE: usize> Frame<[T; SIZE], 2> {
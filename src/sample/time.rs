@@ -172,8 +172,93 @@ impl Mul<u32> for SampleCount {
     }
 }
 
+/// Straight, dotted (x1.5) or triplet (x2/3) note length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DivisionModifier {
+    Straight,
+    Dotted,
+    Triplet,
+}
+
+/// Musical note length relative to the tempo, e.g. 1/4, dotted 1/8 or 1/16 triplet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteDivision {
+    /// Note length as a fraction of whole note, i.e. 4 is a quarter note
+    pub denominator: u8,
+    pub modifier: DivisionModifier,
+}
+
+impl Display for NoteDivision {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "1/{}", self.denominator)?;
+
+        match self.modifier {
+            DivisionModifier::Straight => Ok(()),
+            DivisionModifier::Dotted => write!(f, "."),
+            DivisionModifier::Triplet => write!(f, "T"),
+        }
+    }
+}
+
+impl NoteDivision {
+    pub const QUARTER: Self = Self::straight(4);
+
+    #[inline]
+    pub const fn straight(denominator: u8) -> Self {
+        Self {
+            denominator,
+            modifier: DivisionModifier::Straight,
+        }
+    }
+
+    #[inline]
+    pub const fn dotted(denominator: u8) -> Self {
+        Self {
+            denominator,
+            modifier: DivisionModifier::Dotted,
+        }
+    }
+
+    #[inline]
+    pub const fn triplet(denominator: u8) -> Self {
+        Self {
+            denominator,
+            modifier: DivisionModifier::Triplet,
+        }
+    }
+
+    /// Length in beats (quarter notes)
+    #[inline]
+    pub fn beats(&self) -> f32 {
+        let beats = 4.0 / self.denominator.max(1) as f32;
+
+        match self.modifier {
+            DivisionModifier::Straight => beats,
+            DivisionModifier::Dotted => beats * 1.5,
+            DivisionModifier::Triplet => beats * 2.0 / 3.0,
+        }
+    }
+
+    /// Length in (fractional) samples at given tempo
+    #[inline]
+    pub fn samples(&self, bpm: f32, sample_rate: u32) -> f32 {
+        self.beats() * 60.0 / bpm * sample_rate as f32
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::NoteDivision;
+
+    #[test]
+    fn note_division_samples() {
+        const SR: u32 = 48_000;
+
+        assert_eq!(NoteDivision::QUARTER.samples(120.0, SR), 24_000.0);
+        assert_eq!(NoteDivision::dotted(8).samples(120.0, SR), 18_000.0);
+        assert_eq!(NoteDivision::triplet(8).samples(120.0, SR), 8_000.0);
+        assert_eq!(NoteDivision::straight(1).samples(60.0, SR), 192_000.0);
+    }
 
     // #[test]
     // fn constants() {
@@ -1,12 +1,25 @@
 use crate::{
     daw::channel_rack::Instrument,
-    midi::event::MidiEventListener,
-    modx::{Modulate as _, env::EnvProps, lfo::LfoProps, mod_pack::ModPack},
-    osc::{OpParams, OpProps, Osc, clock::Clock},
+    midi::event::{MidiEventListener, MIDI_CC_MOD_WHEEL, MIDI_CC_SUSTAIN},
+    modx::{
+        env::EnvProps,
+        lfo::LfoProps,
+        matrix::{ModMatrix, ModRoute, ModSource},
+        mod_pack::ModTarget,
+    },
+    osc::{OpProps, Osc, clock::Clock},
+    param::f32::{SignedUnitInterval, UnitInterval},
     sample::Frame,
-    voice::{Voice, VoiceParams, controller::VoicesController},
+    voice::{
+        Glide, Voice, VoiceParams,
+        controller::{NotePriority, Polyphony, VoicesController},
+        filter::FilterProps,
+    },
 };
 
+/// Pitch bend range in semitones synths start with
+pub const DEFAULT_PITCH_BEND_RANGE: u8 = 2;
+
 #[derive(Clone)]
 pub struct Synth<
     O: Osc,
@@ -18,12 +31,19 @@ pub struct Synth<
     lfo_props: [LfoProps; LFOS],
     env_props: [EnvProps; ENVS],
 
-    /// Global ADSRs (envelopes) and LFOs
-    mods: ModPack<LFOS, ENVS, OSCS>,
+    /// Routing of voices LFOs and envelopes to modulation targets
+    matrix: ModMatrix,
 
     op_props: [OpProps<'static, O, OSCS>; OSCS],
 
+    filter_props: FilterProps,
+
     voices: VoicesController<O, VOICES, LFOS, ENVS, OSCS>,
+
+    /// Pitch bend range in semitones up and down
+    pitch_bend_range: u8,
+    pitch_bend: SignedUnitInterval,
+    mod_wheel: UnitInterval,
 }
 
 impl<O: Osc + 'static, const VOICES: usize, const LFOS: usize, const ENVS: usize, const OSCS: usize>
@@ -31,37 +51,17 @@ impl<O: Osc + 'static, const VOICES: usize, const LFOS: usize, const ENVS: usize
 {
     #[inline(always)]
     fn tick(&mut self, clock: &Clock) -> Frame {
-        let global_pitch_mod = self.mods.tick(
-            clock,
-            crate::modx::mod_pack::ModTarget::GlobalPitch,
-            &self.lfo_props,
-            &self.env_props,
-        );
-
-        // Note: Need array allocation because we cannot pass slice (params are modulated) and don't want a vector
-        let op_params = core::array::from_fn(|index| OpParams {
-            props: self.op_props[index].modulated(|target| {
-                self.mods
-                    .tick(clock, target, &self.lfo_props, &self.env_props)
-            }),
-            pitch_mod: global_pitch_mod,
-        });
-
-        let amp_mod = self.mods.tick(
-            clock,
-            crate::modx::mod_pack::ModTarget::GlobalLevel,
-            &self.lfo_props,
-            &self.env_props,
-        );
-
         let frame = self.voices.tick(
             clock,
             &VoiceParams {
                 env_params: &self.env_props,
                 lfo_params: &self.lfo_props,
-                amp_mod,
+                matrix: &self.matrix,
+                filter: &self.filter_props,
+                pitch_bend: self.pitch_bend.inner() * self.pitch_bend_range as f32 / 12.0,
+                mod_wheel: self.mod_wheel,
             },
-            &op_params,
+            &self.op_props,
         );
 
         frame
@@ -80,10 +80,26 @@ impl<O: Osc + 'static, const VOICES: usize, const LFOS: usize, const ENVS: usize
 
         ui.vertical(|ui| {
             ui.horizontal(|ui| {
-                self.voices.egui(ui, params);
+                ui.vertical(|ui| {
+                    self.voices.egui(ui, params);
+                    ui.add(
+                        egui::DragValue::new(&mut self.pitch_bend_range)
+                            .clamp_range(0..=48)
+                            .prefix("Bend range ")
+                            .suffix("st"),
+                    );
+
+                    ui.label("Algorithm");
+                    crate::osc::OpAlgorithm::each().for_each(|algorithm| {
+                        if ui.button(format!("{algorithm}")).clicked() {
+                            algorithm.apply(&mut self.op_props);
+                        }
+                    });
+                });
                 self.op_props
                     .iter_mut()
                     .for_each(|props| props.egui(ui, params));
+                self.filter_props.egui(ui, params);
             });
 
             ui.horizontal(|ui| {
@@ -95,6 +111,8 @@ impl<O: Osc + 'static, const VOICES: usize, const LFOS: usize, const ENVS: usize
                     env.egui(ui, params);
                 });
             });
+
+            self.matrix.egui::<LFOS, ENVS, OSCS>(ui);
         });
     }
 }
@@ -109,7 +127,6 @@ impl<O: Osc + 'static, const VOICES: usize, const LFOS: usize, const ENVS: usize
         note: crate::midi::note::Note,
         velocity: crate::param::f32::UnitInterval,
     ) {
-        self.mods.note_on(clock, note, velocity);
         self.voices.note_on(clock, note, velocity);
     }
 
@@ -120,24 +137,96 @@ impl<O: Osc + 'static, const VOICES: usize, const LFOS: usize, const ENVS: usize
         note: crate::midi::note::Note,
         velocity: crate::param::f32::UnitInterval,
     ) {
-        self.mods.note_off(clock, note, velocity);
         self.voices.note_off(clock, note, velocity);
     }
+
+    #[inline]
+    fn pitch_bend(&mut self, clock: &Clock, bend: SignedUnitInterval) {
+        let _ = clock;
+        self.pitch_bend = bend;
+    }
+
+    #[inline]
+    fn control_change(&mut self, clock: &Clock, controller: u8, value: UnitInterval) {
+        match controller {
+            MIDI_CC_MOD_WHEEL => self.mod_wheel = value,
+            MIDI_CC_SUSTAIN => self
+                .voices
+                .set_sustain(clock, value >= UnitInterval::EQUILIBRIUM),
+            _ => {}
+        }
+    }
 }
 
 impl<O: Osc + 'static, const VOICES: usize, const LFOS: usize, const ENVS: usize, const OSCS: usize>
     Synth<O, VOICES, LFOS, ENVS, OSCS>
 {
     pub fn new(sample_rate: u32, osc_props: impl Fn(usize) -> O::Props<'static>) -> Self {
+        let mut matrix = ModMatrix::new();
+
+        // The first envelope is the amp envelope by default
+        if ENVS > 0 {
+            let _ = matrix.push(ModRoute::new(
+                ModSource::Env(0),
+                ModTarget::GlobalLevel,
+                SignedUnitInterval::MAX,
+            ));
+        }
+
         Self {
             lfo_props: core::array::from_fn(|index| LfoProps::new(index)),
             env_props: core::array::from_fn(|index| EnvProps::new(index, sample_rate)),
-            mods: ModPack::new(),
+            matrix,
             op_props: core::array::from_fn(|index| OpProps::new(index, osc_props(index))),
+            filter_props: FilterProps::new(),
             voices: VoicesController::new(|_| Voice::new(|_| O::default())),
+            pitch_bend_range: DEFAULT_PITCH_BEND_RANGE,
+            pitch_bend: SignedUnitInterval::EQUILIBRIUM,
+            mod_wheel: UnitInterval::MIN,
         }
     }
 
+    #[inline(always)]
+    pub fn pitch_bend_range(&self) -> u8 {
+        self.pitch_bend_range
+    }
+
+    #[inline(always)]
+    pub fn set_pitch_bend_range(&mut self, semitones: u8) {
+        self.pitch_bend_range = semitones;
+    }
+
+    #[inline(always)]
+    pub fn polyphony(&self) -> &Polyphony<VOICES> {
+        self.voices.polyphony()
+    }
+
+    /// See [`VoicesController::set_polyphony`]
+    #[inline(always)]
+    pub fn set_polyphony(&mut self, clock: &Clock, polyphony: Polyphony<VOICES>) {
+        self.voices.set_polyphony(clock, polyphony);
+    }
+
+    #[inline(always)]
+    pub fn glide(&self) -> Glide {
+        self.voices.glide()
+    }
+
+    #[inline(always)]
+    pub fn set_glide(&mut self, glide: Glide) {
+        self.voices.set_glide(glide);
+    }
+
+    #[inline(always)]
+    pub fn note_priority(&self) -> NotePriority {
+        self.voices.note_priority()
+    }
+
+    #[inline(always)]
+    pub fn set_note_priority(&mut self, note_priority: NotePriority) {
+        self.voices.set_note_priority(note_priority);
+    }
+
     #[inline(always)]
     pub fn props_mut(&mut self) -> &mut [OpProps<'static, O, OSCS>] {
         &mut self.op_props
@@ -152,13 +241,26 @@ impl<O: Osc + 'static, const VOICES: usize, const LFOS: usize, const ENVS: usize
     pub fn env_mut(&mut self) -> &mut [EnvProps] {
         &mut self.env_props
     }
+
+    #[inline(always)]
+    pub fn matrix_mut(&mut self) -> &mut ModMatrix {
+        &mut self.matrix
+    }
+
+    #[inline(always)]
+    pub fn filter_mut(&mut self) -> &mut FilterProps {
+        &mut self.filter_props
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        daw::channel_rack::Instrument, midi::event::MidiEventListener, osc::clock::Clock,
-        param::f32::UnitInterval, wavetable::synth::create_basic_wavetable_synth,
+        daw::channel_rack::Instrument,
+        midi::event::{MidiEventListener, MIDI_CC_SUSTAIN},
+        midi::note::Note,
+        osc::clock::Clock, param::f32::{HalfUnitInterval, SignedUnitInterval, UnitInterval},
+        sample::Frame, voice::controller::Polyphony, wavetable::synth::create_basic_wavetable_synth,
     };
 
     #[test]
@@ -181,4 +283,141 @@ mod tests {
         //     synth.tick(&clock.with_tick(clock.sample_rate * 11587))
         // );
     }
+
+    #[test]
+    fn per_voice_envelopes() {
+        let clock = Clock::zero(44_000);
+
+        let synth = || {
+            let mut synth = create_basic_wavetable_synth::<2, 0, 1, 1>(clock.sample_rate);
+            synth.env_mut()[0].enabled = true;
+            synth
+        };
+
+        let mut released = synth();
+        released.note_on(&clock, Note::A4, UnitInterval::MAX);
+        released.note_on(&clock.with_tick(100), Note::E5, UnitInterval::MAX);
+        released.note_off(&clock.with_tick(200), Note::A4, UnitInterval::MAX);
+
+        let mut single = synth();
+        single.note_on(&clock.with_tick(100), Note::E5, UnitInterval::MAX);
+
+        // A4 has been released and faded out, E5 is not affected by its release
+        let clock = clock.with_tick(2_000);
+        let frame = single.tick(&clock);
+        assert_ne!(frame, Frame::zero());
+        assert_eq!(released.tick(&clock), frame);
+    }
+
+    #[test]
+    fn pitch_bend_range() {
+        let clock = Clock::zero(44_000).with_tick(100);
+
+        let mut bent = create_basic_wavetable_synth::<1, 0, 0, 1>(clock.sample_rate);
+        bent.set_pitch_bend_range(12);
+        bent.note_on(&clock, Note::A4, UnitInterval::MAX);
+        bent.pitch_bend(&clock, SignedUnitInterval::MAX);
+
+        let mut octave_up = create_basic_wavetable_synth::<1, 0, 0, 1>(clock.sample_rate);
+        octave_up.note_on(&clock, Note::A5, UnitInterval::MAX);
+
+        let clock = clock.with_tick(150);
+        assert_eq!(bent.tick(&clock), octave_up.tick(&clock));
+
+        // Bending down lowers the pitch as much
+        let clock = clock.with_tick(100);
+        let mut bent = create_basic_wavetable_synth::<1, 0, 0, 1>(clock.sample_rate);
+        bent.set_pitch_bend_range(12);
+        bent.note_on(&clock, Note::A4, UnitInterval::MAX);
+        bent.pitch_bend(&clock, SignedUnitInterval::MIN);
+
+        let mut octave_down = create_basic_wavetable_synth::<1, 0, 0, 1>(clock.sample_rate);
+        octave_down.note_on(&clock, Note::A3, UnitInterval::MAX);
+
+        let clock = clock.with_tick(150);
+        let frame = octave_down.tick(&clock);
+        assert_ne!(frame, Frame::zero());
+        assert_eq!(bent.tick(&clock), frame);
+    }
+
+    #[test]
+    fn mono_sustain() {
+        let clock = Clock::zero(44_000);
+
+        let mut synth = create_basic_wavetable_synth::<2, 0, 1, 1>(clock.sample_rate);
+        synth.env_mut()[0].enabled = true;
+        synth.set_polyphony(&clock, Polyphony::Mono);
+
+        // Released notes keep sounding while the pedal is down
+        synth.control_change(&clock, MIDI_CC_SUSTAIN, UnitInterval::MAX);
+        synth.note_on(&clock, Note::A4, UnitInterval::MAX);
+        synth.note_off(&clock.with_tick(100), Note::A4, UnitInterval::MAX);
+        assert_ne!(synth.tick(&clock.with_tick(990)), Frame::zero());
+        assert_eq!(synth.voices.active_voices(), 1);
+
+        // A new note takes over the single voice, releasing the pedal releases it
+        synth.note_on(&clock.with_tick(1_000), Note::E5, UnitInterval::MAX);
+        synth.note_off(&clock.with_tick(1_100), Note::E5, UnitInterval::MAX);
+        assert!(synth.voices.has_note(Note::E5));
+        assert!(!synth.voices.has_note(Note::A4));
+
+        synth.control_change(&clock.with_tick(1_200), MIDI_CC_SUSTAIN, UnitInterval::MIN);
+        assert_eq!(synth.tick(&clock.with_tick(2_000)), Frame::zero());
+        assert_eq!(synth.voices.active_voices(), 0);
+    }
+
+    #[test]
+    fn released_voices_go_idle() {
+        let clock = Clock::zero(44_000);
+
+        let mut synth = create_basic_wavetable_synth::<2, 0, 1, 1>(clock.sample_rate);
+        synth.env_mut()[0].enabled = true;
+
+        synth.note_on(&clock, Note::A4, UnitInterval::MAX);
+        synth.note_off(&clock.with_tick(100), Note::A4, UnitInterval::MAX);
+        synth.tick(&clock.with_tick(110));
+        assert_eq!(synth.voices.active_voices(), 1);
+
+        // Release is over
+        assert_eq!(synth.tick(&clock.with_tick(1_000)), Frame::zero());
+        assert_eq!(synth.voices.active_voices(), 0);
+    }
+
+    #[test]
+    fn polyphony_switch_releases_notes() {
+        let clock = Clock::zero(44_000);
+        let unison = Polyphony::Unison {
+            unison: 2,
+            detune: UnitInterval::EQUILIBRIUM,
+            blend: HalfUnitInterval::MAX,
+            stereo_spread: UnitInterval::EQUILIBRIUM,
+        };
+
+        [
+            (Polyphony::Poly, Polyphony::Mono),
+            (unison, Polyphony::Legato),
+            (Polyphony::Mono, Polyphony::Poly),
+            (Polyphony::Legato, Polyphony::Mono),
+        ]
+        .into_iter()
+        .for_each(|(from, to)| {
+            let mut synth = create_basic_wavetable_synth::<4, 0, 1, 1>(clock.sample_rate);
+            synth.env_mut()[0].enabled = true;
+            synth.set_polyphony(&clock, from.clone());
+
+            synth.note_on(&clock, Note::A4, UnitInterval::MAX);
+            synth.note_on(&clock, Note::E5, UnitInterval::MAX);
+            synth.tick(&clock.with_tick(10));
+
+            // Notes held in the previous mode are released by their note-offs in the new one
+            synth.set_polyphony(&clock.with_tick(20), to.clone());
+            synth.note_off(&clock.with_tick(30), Note::A4, UnitInterval::MAX);
+            synth.note_off(&clock.with_tick(30), Note::E5, UnitInterval::MAX);
+
+            assert!(!synth.voices.has_note(Note::A4), "{from:?} to {to:?}");
+            assert!(!synth.voices.has_note(Note::E5), "{from:?} to {to:?}");
+            assert_eq!(synth.tick(&clock.with_tick(1_000)), Frame::zero());
+            assert_eq!(synth.voices.active_voices(), 0, "{from:?} to {to:?}");
+        });
+    }
 }
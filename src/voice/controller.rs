@@ -1,8 +1,8 @@
-use super::{Voice, VoiceParams};
+use super::{Glide, Voice, VoiceParams};
 use crate::{
     macros::debug_assert_unit,
     midi::{event::MidiEventListener, note::Note},
-    osc::{clock::Clock, OpParams, Osc},
+    osc::{clock::Clock, OpProps, Osc},
     param::f32::{HalfUnitInterval, SignedUnitInterval, UnitInterval},
     sample::Frame,
 };
@@ -75,19 +75,76 @@ pub fn voices_stereo_spread(
 #[derive(Clone, Default)]
 struct MonoVoice {
     note: Option<Note>,
+    /// Trigger counter value at the moment the voice was triggered, lower is older
+    triggered_at: u32,
 }
 
-/// The polyphony mode
+/// Capacity of the held notes stack of mono modes, the oldest notes are forgotten on overflow
+pub const HELD_NOTES_MAX: usize = 16;
+
+/// Notes held in mono modes, in order of triggering
 #[derive(Clone)]
-enum Polyphony<const MAX_VOICES: usize> {
-    // TODO: Add mono? Restricting to a specific mono voices count
-    Poly {
-        /// The index of the voice played last. Depending on NotePriority next triggered note will replace currently played one if there're no available voices.
-        // TODO: This can be replaced with `next_note_index` that is picked by NotePriority.
-        //  - For last it is the oldest triggered
-        //  - For Highest and lowest the frequency comparison is done to store `next_note_index` with lowest/highest note
-        last_voice_index: u8,
-    },
+struct HeldNotes {
+    notes: [Option<(Note, UnitInterval)>; HELD_NOTES_MAX],
+    len: usize,
+}
+
+impl HeldNotes {
+    const fn new() -> Self {
+        Self {
+            notes: [None; HELD_NOTES_MAX],
+            len: 0,
+        }
+    }
+
+    #[inline]
+    fn iter(&self) -> impl Iterator<Item = (Note, UnitInterval)> + '_ {
+        self.notes[..self.len].iter().flatten().copied()
+    }
+
+    fn push(&mut self, note: Note, velocity: UnitInterval) {
+        self.remove(note);
+
+        if self.len == HELD_NOTES_MAX {
+            self.notes.copy_within(1.., 0);
+            self.len -= 1;
+        }
+
+        self.notes[self.len] = Some((note, velocity));
+        self.len += 1;
+    }
+
+    fn remove(&mut self, note: Note) {
+        let position = self.iter().position(|(held, _)| held == note);
+
+        if let Some(position) = position {
+            self.notes.copy_within(position + 1..self.len, position);
+            self.len -= 1;
+            self.notes[self.len] = None;
+        }
+    }
+
+    /// The held note which sounds according to note priority
+    #[inline]
+    fn active(&self, note_priority: NotePriority) -> Option<(Note, UnitInterval)> {
+        match note_priority {
+            NotePriority::Lowest => self.iter().min_by_key(|&(note, _)| note),
+            NotePriority::Highest => self.iter().max_by_key(|&(note, _)| note),
+            NotePriority::Last => self.iter().last(),
+            NotePriority::OnlyFree => self.iter().next(),
+        }
+    }
+}
+
+/// The polyphony mode
+#[derive(Debug, Clone)]
+pub enum Polyphony<const MAX_VOICES: usize> {
+    /// One voice per played note
+    Poly,
+    /// Single voice retriggered by each note
+    Mono,
+    /// Single voice which is only triggered when no other note is held, otherwise it just changes pitch
+    Legato,
     Unison {
         /// Unison voices count per voice group (one group per played note)
         unison: usize,
@@ -108,26 +165,26 @@ impl<const MAX_VOICES: usize> PartialEq for Polyphony<MAX_VOICES> {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (Self::Poly { .. }, Self::Poly { .. }) | (Self::Unison { .. }, Self::Unison { .. }) => {
-                true
-            }
+            (Self::Poly, Self::Poly)
+            | (Self::Mono, Self::Mono)
+            | (Self::Legato, Self::Legato)
+            | (Self::Unison { .. }, Self::Unison { .. }) => true,
             _ => false,
         }
     }
 }
 
-// TODO: Use
 /// The order by which newly triggered notes take precedence over currently playing notes
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum NotePriority {
     /// Lower-frequency notes take precedence over higher-frequency notes when there's not enough free voices to play triggered note
     Lowest,
     /// Higher-frequency notes take precedence over lower-frequency notes
     Highest,
     /// If there's not enough free voices, the oldest triggered note is replaced with a new one
+    #[default]
     Last,
-    // TODO: In case gliding/portamento added, should notes be stacked in a queue so when voice is freed it glides to new note?
-    /// The note is only played if there're enough free voices
+    /// The note is only played if there're enough free voices. In mono modes, the first held note keeps playing.
     OnlyFree,
 }
 
@@ -143,24 +200,37 @@ pub struct VoicesController<
     /// Voices root notes.
     voices_notes: [MonoVoice; VOICES],
     polyphony: Polyphony<VOICES>,
+    note_priority: NotePriority,
+    /// Count of triggered notes, used to find the oldest voices
+    triggers: u32,
+    /// Notes held in mono modes
+    held: HeldNotes,
+    /// Portamento in mono modes
+    glide: Glide,
+    /// Sustain pedal is down
+    sustain: bool,
+    /// Notes released while sustain pedal is down, a bit per MIDI note
+    sustained: u128,
 }
 
 #[cfg(feature = "egui")]
-impl<O: Osc, const VOICES: usize, const LFOS: usize, const ENVS: usize, const OSCS: usize>
-    crate::param::ui::EguiComponent for VoicesController<O, VOICES, LFOS, ENVS, OSCS>
+impl<
+        O: Osc + 'static,
+        const VOICES: usize,
+        const LFOS: usize,
+        const ENVS: usize,
+        const OSCS: usize,
+    > crate::param::ui::EguiComponent for VoicesController<O, VOICES, LFOS, ENVS, OSCS>
 {
     fn egui(&mut self, ui: &mut egui::Ui, params: crate::param::ui::DefaultUiParams) {
         ui.vertical(|ui| {
             // TODO: Move to Polyphony::egui method
+            let mut polyphony = self.polyphony.clone();
+            ui.radio_value(&mut polyphony, Polyphony::Poly, "Poly");
+            ui.radio_value(&mut polyphony, Polyphony::Mono, "Mono");
+            ui.radio_value(&mut polyphony, Polyphony::Legato, "Legato");
             ui.radio_value(
-                &mut self.polyphony,
-                Polyphony::Poly {
-                    last_voice_index: 0,
-                },
-                "Poly",
-            );
-            ui.radio_value(
-                &mut self.polyphony,
+                &mut polyphony,
                 Polyphony::Unison {
                     unison: 1,
                     detune: UnitInterval::EQUILIBRIUM,
@@ -169,6 +239,7 @@ impl<O: Osc, const VOICES: usize, const LFOS: usize, const ENVS: usize, const OS
                 },
                 "Unison",
             );
+            self.set_polyphony(&params.clock, polyphony);
 
             if let Polyphony::Unison {
                 unison,
@@ -230,6 +301,46 @@ impl<O: Osc, const VOICES: usize, const LFOS: usize, const ENVS: usize, const OS
                 ui.add(blend.widget().text("Blend"));
                 ui.add(stereo_spread.widget().text("Stereo"));
             }
+
+            if let Polyphony::Mono | Polyphony::Legato = self.polyphony {
+                use crate::sample::time::SampleCount;
+
+                let clock = params.clock;
+                let glide_time = SampleCount::from_millis(100, clock.sample_rate);
+                let glide_clamp = Some((
+                    SampleCount::from_millis(1, clock.sample_rate),
+                    SampleCount::from_secs(5, clock.sample_rate),
+                ));
+
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.glide, Glide::Off, "No glide");
+                    let time = matches!(self.glide, Glide::Time(_));
+                    if ui.radio(time, "Time").clicked() && !time {
+                        self.glide = Glide::Time(glide_time);
+                    }
+                    let rate = matches!(self.glide, Glide::Rate(_));
+                    if ui.radio(rate, "Rate").clicked() && !rate {
+                        self.glide = Glide::Rate(glide_time);
+                    }
+                });
+
+                match &mut self.glide {
+                    Glide::Off => {}
+                    Glide::Time(time) => {
+                        ui.add(time.widget(clock, glide_clamp).text("Glide time"));
+                    }
+                    Glide::Rate(rate) => {
+                        ui.add(rate.widget(clock, glide_clamp).text("Glide per octave"));
+                    }
+                }
+            }
+
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.note_priority, NotePriority::Last, "Last");
+                ui.radio_value(&mut self.note_priority, NotePriority::Lowest, "Lowest");
+                ui.radio_value(&mut self.note_priority, NotePriority::Highest, "Highest");
+                ui.radio_value(&mut self.note_priority, NotePriority::OnlyFree, "Only free");
+            });
         });
     }
 }
@@ -243,100 +354,98 @@ impl<
     > MidiEventListener for VoicesController<O, VOICES, LFOS, ENVS, OSCS>
 {
     fn note_on(&mut self, clock: &Clock, note: Note, velocity: UnitInterval) {
-        // TODO: What to do if there're no available voices? Queue? Ignore?
-        // TODO: Priority
+        let mono = matches!(self.polyphony, Polyphony::Mono | Polyphony::Legato);
+
+        // Sustained note played again is retriggered, mono modes move it to the top of held notes
+        if self.take_sustained(note) && !mono {
+            self.release(clock, note, UnitInterval::MIN);
+        }
+
+        if mono {
+            let sounding = self.held.active(self.note_priority);
+            self.held.push(note, velocity);
+            self.mono_transition(clock, sounding, self.held.active(self.note_priority));
+            return;
+        }
 
         // TODO: Is this always right?
         if self.has_note(note) {
             return;
         }
 
-        let mut note_on_voice = |index: usize,
-                                 note: Note,
-                                 velocity: UnitInterval,
-                                 detune: SignedUnitInterval,
-                                 blend: UnitInterval,
-                                 stereo_balance: UnitInterval| {
-            self.voices[index].set_stereo_balance(stereo_balance);
-            self.voices[index].set_detune(blend, detune);
-            self.voices[index].note_on(clock, note, velocity);
-        };
-
-        match &mut self.polyphony {
-            Polyphony::Poly { last_voice_index } => {
-                let oldest_index = (*last_voice_index as usize + 1) % VOICES;
-
-                // TODO: This will find next free voice, but only free as "not triggered" while it can still be in release ADSR stage
-                // Find next free voice, if no free voice found, fallback to the oldest one and overwrite it
-                let index = (oldest_index..oldest_index + VOICES)
-                    .find(|index| self.voices_notes[index % VOICES].note.is_none())
-                    .unwrap_or(oldest_index)
-                    % VOICES;
-
-                note_on_voice(
-                    index,
-                    note,
-                    velocity,
-                    SignedUnitInterval::EQUILIBRIUM,
-                    UnitInterval::MAX,
-                    UnitInterval::EQUILIBRIUM,
-                );
-                self.voices_notes[index].note = Some(note);
-
-                *last_voice_index = index as u8;
-            }
-            &mut Polyphony::Unison {
+        let (unison, detune, blend, stereo_spread) = match self.polyphony {
+            Polyphony::Poly | Polyphony::Mono | Polyphony::Legato => (
+                1,
+                UnitInterval::MIN,
+                HalfUnitInterval::MIN,
+                UnitInterval::MIN,
+            ),
+            Polyphony::Unison {
                 unison,
                 detune,
                 blend,
                 stereo_spread,
-            } => {
-                // TODO: NotePriority
-                if self
-                    .voices_notes
-                    .iter()
-                    .filter(|voice| voice.note.is_none())
-                    .count()
-                    < unison
-                {
-                    return;
-                }
+            } => (unison, detune, blend, stereo_spread),
+        };
 
-                self.voices_notes
-                    .iter_mut()
-                    .enumerate()
-                    .filter_map(|(index, voice)| {
-                        if voice.note.is_none() {
-                            Some((index, voice))
-                        } else {
-                            None
-                        }
-                    })
-                    .take(unison)
-                    .zip(
-                        voices_detune(unison, detune, blend)
-                            .zip(voices_stereo_spread(unison, stereo_spread)),
-                    )
-                    .for_each(
-                        |((voice_index, voice), ((detune, blend), stereo_balance))| {
-                            note_on_voice(
-                                voice_index,
-                                note,
-                                velocity,
-                                detune,
-                                blend,
-                                stereo_balance,
-                            );
-                            voice.note = Some(note);
-                        },
-                    );
-            }
+        if !self.free_voices(clock, note, unison) {
+            return;
         }
+
+        self.triggers = self.triggers.wrapping_add(1);
+
+        voices_detune(unison, detune, blend)
+            .zip(voices_stereo_spread(unison, stereo_spread))
+            .for_each(|((detune, blend), stereo_balance)| {
+                // Voices are freed beforehand, so there's always one
+                let Some(index) = self.quietest_free_voice() else {
+                    return;
+                };
+
+                let voice = &mut self.voices[index];
+                voice.set_stereo_balance(stereo_balance);
+                voice.set_detune(blend, detune);
+                voice.note_on(clock, note, velocity);
+
+                self.voices_notes[index] = MonoVoice {
+                    note: Some(note),
+                    triggered_at: self.triggers,
+                };
+            });
     }
 
     #[inline]
     fn note_off(&mut self, clock: &Clock, note: Note, velocity: UnitInterval) {
-        let _ = velocity;
+        if self.sustain {
+            self.sustained |= sustained_bit(note);
+            return;
+        }
+
+        self.release(clock, note, velocity);
+    }
+}
+
+#[inline]
+const fn sustained_bit(note: Note) -> u128 {
+    1 << note as u8
+}
+
+impl<
+        O: Osc + 'static,
+        const VOICES: usize,
+        const LFOS: usize,
+        const ENVS: usize,
+        const OSCS: usize,
+    > VoicesController<O, VOICES, LFOS, ENVS, OSCS>
+{
+    /// Release the note regardless of sustain pedal
+    fn release(&mut self, clock: &Clock, note: Note, velocity: UnitInterval) {
+        if let Polyphony::Mono | Polyphony::Legato = self.polyphony {
+            let sounding = self.held.active(self.note_priority);
+            self.held.remove(note);
+            self.mono_transition(clock, sounding, self.held.active(self.note_priority));
+            return;
+        }
 
         self.voices_notes
             .iter_mut()
@@ -361,11 +470,187 @@ impl<
     pub fn new(f: impl Fn(usize) -> Voice<O, LFOS, ENVS, OSCS>) -> Self {
         Self {
             voices: core::array::from_fn(f),
-            polyphony: Polyphony::Poly {
-                last_voice_index: 0,
-            },
-            voices_notes: core::array::from_fn(|_| MonoVoice { note: None }),
+            polyphony: Polyphony::Poly,
+            voices_notes: core::array::from_fn(|_| MonoVoice::default()),
+            note_priority: NotePriority::default(),
+            triggers: 0,
+            held: HeldNotes::new(),
+            glide: Glide::Off,
+            sustain: false,
+            sustained: 0,
+        }
+    }
+
+    #[inline]
+    pub fn sustain(&self) -> bool {
+        self.sustain
+    }
+
+    /// Press or release sustain pedal. While it's down, note-offs are held back until it's released.
+    pub fn set_sustain(&mut self, clock: &Clock, sustain: bool) {
+        self.sustain = sustain;
+
+        if !sustain {
+            let sustained = core::mem::take(&mut self.sustained);
+
+            Note::each()
+                .filter(|&note| sustained & sustained_bit(note) != 0)
+                .for_each(|note| self.release(clock, note, UnitInterval::MIN));
+        }
+    }
+
+    /// Forget the note is sustained, returning whether it was
+    #[inline]
+    fn take_sustained(&mut self, note: Note) -> bool {
+        let sustained = self.sustained & sustained_bit(note) != 0;
+        self.sustained &= !sustained_bit(note);
+        sustained
+    }
+
+    #[inline]
+    pub fn polyphony(&self) -> &Polyphony<VOICES> {
+        &self.polyphony
+    }
+
+    /// Switch the polyphony mode. Changing the mode releases all sounding notes, as voices and held notes of one mode mean nothing to another.
+    pub fn set_polyphony(&mut self, clock: &Clock, polyphony: Polyphony<VOICES>) {
+        if polyphony != self.polyphony {
+            self.release_all(clock);
+        }
+        self.polyphony = polyphony;
+    }
+
+    /// Release all voices and forget held and sustained notes
+    fn release_all(&mut self, clock: &Clock) {
+        self.voices_notes
+            .iter_mut()
+            .enumerate()
+            .for_each(|(index, voice)| {
+                if let Some(note) = voice.note.take() {
+                    self.voices[index].note_off(clock, note, UnitInterval::MIN);
+                }
+            });
+        self.held = HeldNotes::new();
+        self.sustained = 0;
+    }
+
+    #[inline]
+    pub fn glide(&self) -> Glide {
+        self.glide
+    }
+
+    #[inline]
+    pub fn set_glide(&mut self, glide: Glide) {
+        self.glide = glide;
+    }
+
+    /// Switch the mono voice from the note sounding before a held notes change to the one sounding after it
+    fn mono_transition(
+        &mut self,
+        clock: &Clock,
+        from: Option<(Note, UnitInterval)>,
+        to: Option<(Note, UnitInterval)>,
+    ) {
+        const VOICE: usize = 0;
+
+        let voice = &mut self.voices[VOICE];
+
+        match (from, to) {
+            (Some((from, _)), Some((to, _))) if from == to => return,
+            (Some(_), Some((to, velocity))) => {
+                voice.glide_to(to, self.glide);
+                if self.polyphony != Polyphony::Legato {
+                    voice.retrigger(clock, to, velocity);
+                }
+            }
+            (None, Some((to, velocity))) => {
+                voice.set_detune(UnitInterval::MAX, SignedUnitInterval::EQUILIBRIUM);
+                voice.set_stereo_balance(UnitInterval::EQUILIBRIUM);
+                voice.note_on(clock, to, velocity);
+            }
+            (Some((from, _)), None) => {
+                voice.note_off(clock, from, UnitInterval::MIN);
+                self.voices_notes[VOICE].note = None;
+                return;
+            }
+            (None, None) => return,
+        }
+
+        self.triggers = self.triggers.wrapping_add(1);
+        self.voices_notes[VOICE] = MonoVoice {
+            note: to.map(|(note, _)| note),
+            triggered_at: self.triggers,
+        };
+    }
+
+    #[inline]
+    pub fn note_priority(&self) -> NotePriority {
+        self.note_priority
+    }
+
+    #[inline]
+    pub fn set_note_priority(&mut self, note_priority: NotePriority) {
+        self.note_priority = note_priority;
+    }
+
+    /// Make sure there are `count` voices not playing any note, releasing notes according to [`NotePriority`]. Returns `false` if the new note must not be played.
+    fn free_voices(&mut self, clock: &Clock, note: Note, count: usize) -> bool {
+        if count > VOICES {
+            return false;
+        }
+
+        while self
+            .voices_notes
+            .iter()
+            .filter(|voice| voice.note.is_none())
+            .count()
+            < count
+        {
+            let held = self
+                .voices_notes
+                .iter()
+                .filter_map(|voice| voice.note.map(|note| (note, voice.triggered_at)));
+
+            let stolen = match self.note_priority {
+                NotePriority::Lowest => held
+                    .map(|(held, _)| held)
+                    .max()
+                    .filter(|&highest| highest > note),
+                NotePriority::Highest => held
+                    .map(|(held, _)| held)
+                    .min()
+                    .filter(|&lowest| lowest < note),
+                NotePriority::Last => held
+                    .min_by_key(|&(_, triggered_at)| triggered_at)
+                    .map(|(oldest, _)| oldest),
+                NotePriority::OnlyFree => None,
+            };
+
+            let Some(stolen) = stolen else {
+                return false;
+            };
+
+            self.take_sustained(stolen);
+            self.release(clock, stolen, UnitInterval::MIN);
         }
+
+        true
+    }
+
+    /// The voice not playing any note, idle voices come first. Otherwise it's the quietest releasing voice, or the oldest among equally quiet ones.
+    fn quietest_free_voice(&self) -> Option<usize> {
+        self.voices_notes
+            .iter()
+            .zip(self.voices.iter())
+            .enumerate()
+            .filter(|(_, (voice_note, _))| voice_note.note.is_none())
+            .min_by(|(_, (a_note, a)), (_, (b_note, b))| {
+                b.is_idle()
+                    .cmp(&a.is_idle())
+                    .then(a.level().inner().total_cmp(&b.level().inner()))
+                    .then(a_note.triggered_at.cmp(&b_note.triggered_at))
+            })
+            .map(|(index, _)| index)
     }
 
     // pub fn voice_n(&self, index: usize) -> &Voice<O, LFOS, ENVS, OSCS> {
@@ -390,6 +675,12 @@ impl<
     //     self.voices.iter_mut()
     // }
 
+    /// Count of voices which are held or releasing
+    #[inline]
+    pub fn active_voices(&self) -> usize {
+        self.voices.iter().filter(|voice| !voice.is_idle()).count()
+    }
+
     #[inline(always)]
     pub fn has_note(&self, note: Note) -> bool {
         self.voices_notes
@@ -413,15 +704,215 @@ impl<
         &mut self,
         clock: &Clock,
         params: &VoiceParams<'a, OSCS>,
-        op_params: &[OpParams<'static, O, OSCS>; OSCS],
+        op_props: &[OpProps<'static, O, OSCS>; OSCS],
     ) -> Frame {
         self.voices
             .iter_mut()
+            .filter(|voice| !voice.is_idle())
             .map(|voice| {
                 voice
-                    .tick(clock, &params, op_params)
+                    .tick(clock, &params, op_props)
                     .map(|sample| sample / VOICES as f32)
             })
             .sum()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{NotePriority, Polyphony, VoicesController};
+    use crate::{
+        midi::{event::MidiEventListener, note::Note},
+        osc::clock::Clock,
+        param::f32::{HalfUnitInterval, UnitInterval},
+        sample::time::SampleCount,
+        voice::{Glide, Voice, VoiceState},
+        wavetable::osc::WavetableOsc,
+    };
+    use alloc::vec::Vec;
+
+    type Controller<const VOICES: usize> = VoicesController<WavetableOsc<1, 1>, VOICES, 0, 0, 1>;
+
+    const CLOCK: Clock = Clock {
+        sample_rate: 48_000,
+        tick: 0,
+    };
+
+    fn new_controller<const VOICES: usize>(note_priority: NotePriority) -> Controller<VOICES> {
+        let mut controller = Controller::new(|_| Voice::new(|_| WavetableOsc::default()));
+        controller.set_note_priority(note_priority);
+        controller
+    }
+
+    fn play<const VOICES: usize>(controller: &mut Controller<VOICES>, notes: &[Note]) {
+        notes
+            .iter()
+            .for_each(|&note| controller.note_on(&CLOCK, note, UnitInterval::MAX));
+    }
+
+    fn held<const VOICES: usize>(controller: &Controller<VOICES>) -> Vec<Note> {
+        let mut held = controller
+            .voices_notes
+            .iter()
+            .filter_map(|voice| voice.note)
+            .collect::<Vec<_>>();
+        held.sort();
+        held
+    }
+
+    #[test]
+    fn last_priority() {
+        let mut controller = new_controller::<2>(NotePriority::Last);
+        play(&mut controller, &[Note::C4, Note::E4, Note::G4]);
+        assert_eq!(held(&controller), [Note::E4, Note::G4]);
+    }
+
+    #[test]
+    fn lowest_priority() {
+        let mut controller = new_controller::<2>(NotePriority::Lowest);
+        play(&mut controller, &[Note::C4, Note::E4, Note::G4]);
+        assert_eq!(held(&controller), [Note::C4, Note::E4]);
+
+        play(&mut controller, &[Note::A3]);
+        assert_eq!(held(&controller), [Note::A3, Note::C4]);
+    }
+
+    #[test]
+    fn highest_priority() {
+        let mut controller = new_controller::<2>(NotePriority::Highest);
+        play(&mut controller, &[Note::C4, Note::E4, Note::A3]);
+        assert_eq!(held(&controller), [Note::C4, Note::E4]);
+
+        play(&mut controller, &[Note::G4]);
+        assert_eq!(held(&controller), [Note::E4, Note::G4]);
+    }
+
+    #[test]
+    fn only_free_priority() {
+        let mut controller = new_controller::<2>(NotePriority::OnlyFree);
+        play(&mut controller, &[Note::C4, Note::E4, Note::G4]);
+        assert_eq!(held(&controller), [Note::C4, Note::E4]);
+
+        controller.note_off(&CLOCK, Note::C4, UnitInterval::MAX);
+        play(&mut controller, &[Note::G4]);
+        assert_eq!(held(&controller), [Note::E4, Note::G4]);
+    }
+
+    #[test]
+    fn unison_priority() {
+        let unison = Polyphony::Unison {
+            unison: 2,
+            detune: UnitInterval::EQUILIBRIUM,
+            blend: HalfUnitInterval::MAX,
+            stereo_spread: UnitInterval::EQUILIBRIUM,
+        };
+
+        let mut controller = new_controller::<4>(NotePriority::Last);
+        controller.set_polyphony(&CLOCK, unison.clone());
+        play(&mut controller, &[Note::C4, Note::E4, Note::G4]);
+        assert_eq!(held(&controller), [Note::E4, Note::E4, Note::G4, Note::G4]);
+
+        let mut controller = new_controller::<4>(NotePriority::OnlyFree);
+        controller.set_polyphony(&CLOCK, unison);
+        play(&mut controller, &[Note::C4, Note::E4, Note::G4]);
+        assert_eq!(held(&controller), [Note::C4, Note::C4, Note::E4, Note::E4]);
+    }
+
+    #[test]
+    fn steal_quietest_releasing() {
+        let mut controller = new_controller::<3>(NotePriority::Last);
+        play(&mut controller, &[Note::C4, Note::E4, Note::G4]);
+        [Note::C4, Note::E4, Note::G4]
+            .into_iter()
+            .for_each(|note| controller.note_off(&CLOCK, note, UnitInterval::MAX));
+
+        controller.voices[0].level = UnitInterval::new(0.5);
+        controller.voices[1].level = UnitInterval::new(0.1);
+        controller.voices[2].level = UnitInterval::new(0.5);
+        play(&mut controller, &[Note::A4]);
+        assert_eq!(controller.voices_notes[1].note, Some(Note::A4));
+
+        // Equally quiet voices, the oldest one is taken
+        play(&mut controller, &[Note::B4]);
+        assert_eq!(controller.voices_notes[0].note, Some(Note::B4));
+    }
+
+    #[test]
+    fn sustain_pedal() {
+        let mut controller = new_controller::<2>(NotePriority::Last);
+        controller.set_sustain(&CLOCK, true);
+        play(&mut controller, &[Note::C4]);
+        controller.note_off(&CLOCK, Note::C4, UnitInterval::MAX);
+        assert_eq!(held(&controller), [Note::C4]);
+
+        // Sustained note played again takes a new voice
+        play(&mut controller, &[Note::C4]);
+        assert_eq!(controller.voices_notes[0].note, None);
+        assert_eq!(controller.voices_notes[1].note, Some(Note::C4));
+        controller.note_off(&CLOCK, Note::C4, UnitInterval::MAX);
+
+        controller.set_sustain(&CLOCK, false);
+        assert_eq!(held(&controller), []);
+    }
+
+    #[test]
+    fn prefer_idle_voices() {
+        let mut controller = new_controller::<3>(NotePriority::Last);
+        assert_eq!(controller.active_voices(), 0);
+
+        play(&mut controller, &[Note::C4, Note::E4, Note::G4]);
+        [Note::C4, Note::E4]
+            .into_iter()
+            .for_each(|note| controller.note_off(&CLOCK, note, UnitInterval::MAX));
+        assert_eq!(controller.voices[0].state(), VoiceState::Releasing);
+        assert_eq!(controller.active_voices(), 3);
+
+        // The oldest releasing voice is silent already but still not finished, the idle one is taken
+        controller.voices[0].level = UnitInterval::MIN;
+        controller.voices[1].state = VoiceState::Idle;
+        assert_eq!(controller.active_voices(), 2);
+        play(&mut controller, &[Note::A4]);
+        assert_eq!(controller.voices_notes[1].note, Some(Note::A4));
+        assert_eq!(controller.voices[0].state(), VoiceState::Releasing);
+    }
+
+    #[test]
+    fn mono_held_notes() {
+        let mut controller = new_controller::<2>(NotePriority::Last);
+        controller.set_polyphony(&CLOCK, Polyphony::Mono);
+        play(&mut controller, &[Note::C4, Note::E4, Note::G4]);
+        assert_eq!(held(&controller), [Note::G4]);
+
+        // Releasing the sounding note falls back to the previously held one
+        controller.note_off(&CLOCK, Note::G4, UnitInterval::MAX);
+        assert_eq!(held(&controller), [Note::E4]);
+        assert_eq!(controller.voices[0].root_freq, Note::E4.freq());
+
+        controller.note_off(&CLOCK, Note::C4, UnitInterval::MAX);
+        assert_eq!(held(&controller), [Note::E4]);
+
+        controller.note_off(&CLOCK, Note::E4, UnitInterval::MAX);
+        assert_eq!(held(&controller), []);
+    }
+
+    #[test]
+    fn legato_glide() {
+        let mut controller = new_controller::<1>(NotePriority::Last);
+        controller.set_polyphony(&CLOCK, Polyphony::Legato);
+        controller.set_glide(Glide::Time(SampleCount::new(100)));
+
+        controller.note_on(&CLOCK, Note::C4, UnitInterval::new(0.5));
+        controller.note_on(&CLOCK, Note::C5, UnitInterval::MAX);
+        // Envelopes are not retriggered by the legato note
+        assert_eq!(controller.voices[0].velocity, UnitInterval::new(0.5));
+
+        let voice = &mut controller.voices[0];
+        (0..50).for_each(|_| voice.advance_glide());
+        let halfway = voice.root_freq.inner();
+        let expected = Note::C4.freq().inner() * 2f32.sqrt();
+        assert!((halfway - expected).abs() < 0.1, "{halfway}");
+
+        (0..50).for_each(|_| voice.advance_glide());
+        assert_eq!(voice.root_freq, Note::C5.freq());
+    }
+}
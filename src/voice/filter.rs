@@ -0,0 +1,155 @@
+use crate::{
+    fx::filter::{FilterKind, FilterParams},
+    midi::note::Note,
+    osc::clock::Freq,
+    param::f32::{SignedUnitInterval, UnitInterval},
+};
+#[allow(unused)]
+use num_traits::Float as _;
+
+/// Cutoff modulation range in octaves at full modulation depth
+pub const FILTER_MOD_OCTAVES: f32 = 6.0;
+
+/// The note at which key tracking leaves cutoff untouched
+pub const FILTER_KEY_TRACKING_ROOT: Note = Note::C4;
+
+/// The properties of the voice filter. Global for all voices, each voice filters its own signal.
+#[derive(Debug, Clone)]
+pub struct FilterProps {
+    pub enabled: bool,
+    pub kind: FilterKind,
+    pub cutoff: Freq,
+    pub resonance: UnitInterval,
+    /// Ladder input saturation
+    pub drive: UnitInterval,
+    /// How much cutoff follows the played note, at maximum cutoff moves by an octave per octave
+    pub key_tracking: UnitInterval,
+    /// Index of the filter envelope
+    pub env: usize,
+    /// Depth of the filter envelope on cutoff, negative values close the filter
+    pub env_amount: SignedUnitInterval,
+}
+
+impl Default for FilterProps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "egui")]
+impl crate::param::ui::EguiComponent for FilterProps {
+    fn egui(&mut self, ui: &mut egui::Ui, _params: crate::param::ui::DefaultUiParams) {
+        use crate::fx::filter::svf::SvfMode;
+
+        ui.vertical(|ui| {
+            ui.checkbox(&mut self.enabled, "Filter enabled");
+
+            if !self.enabled {
+                return;
+            }
+
+            SvfMode::each().for_each(|mode| {
+                ui.radio_value(&mut self.kind, FilterKind::Svf(mode), format!("{mode}"));
+            });
+            ui.radio_value(&mut self.kind, FilterKind::Ladder, "Ladder");
+
+            ui.add(
+                self.cutoff
+                    .widget(Some(Freq::Hz(20)..=Freq::kHz(20)))
+                    .text("Cutoff"),
+            );
+            ui.add(self.resonance.widget().text("Resonance"));
+
+            if self.kind == FilterKind::Ladder {
+                ui.add(self.drive.widget().text("Drive"));
+            }
+
+            ui.add(self.key_tracking.widget().text("Key tracking"));
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut self.env).prefix("Env"));
+                ui.add(self.env_amount.widget().text("Env amount"));
+            });
+        });
+    }
+}
+
+impl FilterProps {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            kind: FilterKind::default(),
+            cutoff: Freq::kHz(2),
+            resonance: UnitInterval::MIN,
+            drive: UnitInterval::MIN,
+            key_tracking: UnitInterval::MIN,
+            env: 0,
+            env_amount: SignedUnitInterval::EQUILIBRIUM,
+        }
+    }
+
+    /// Filter parameters for a voice playing `root_freq`. `env` is the value of the filter envelope, modulations are summed [`crate::modx::mod_pack::ModTarget::FilterCutoff`] and [`crate::modx::mod_pack::ModTarget::FilterResonance`] modulations.
+    #[inline]
+    pub fn params(
+        &self,
+        root_freq: Freq,
+        env: Option<UnitInterval>,
+        cutoff_mod: Option<f32>,
+        resonance_mod: Option<f32>,
+    ) -> FilterParams {
+        let key_octaves = self.key_tracking.inner()
+            * (root_freq.inner() / FILTER_KEY_TRACKING_ROOT.freq().inner())
+                .max(f32::EPSILON)
+                .log2();
+
+        let mod_octaves = (env.map(|env| env.inner()).unwrap_or(0.0) * self.env_amount.inner()
+            + cutoff_mod.unwrap_or(0.0))
+            * FILTER_MOD_OCTAVES;
+
+        let resonance = resonance_mod
+            .map(|resonance_mod| UnitInterval::new(self.resonance.inner() + resonance_mod))
+            .unwrap_or(self.resonance);
+
+        FilterParams {
+            kind: self.kind,
+            cutoff: self.cutoff * 2f32.powf(key_octaves + mod_octaves),
+            resonance,
+            drive: self.drive,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FilterProps;
+    use crate::{
+        midi::note::Note,
+        osc::clock::Freq,
+        param::f32::{SignedUnitInterval, UnitInterval},
+    };
+
+    #[test]
+    fn cutoff_modulation() {
+        let mut props = FilterProps::new();
+        props.cutoff = Freq::kHz(1);
+
+        assert_eq!(
+            props.params(Note::C5.freq(), None, None, None).cutoff,
+            Freq::kHz(1)
+        );
+
+        props.key_tracking = UnitInterval::MAX;
+        let tracked = props
+            .params(Note::C5.freq(), None, None, None)
+            .cutoff
+            .inner();
+        assert!((tracked - 2_000.0).abs() < 1.0, "{tracked}");
+
+        props.key_tracking = UnitInterval::MIN;
+        props.env_amount = SignedUnitInterval::new(-1.0 / 6.0);
+        let closed = props
+            .params(Note::C4.freq(), Some(UnitInterval::MAX), None, None)
+            .cutoff
+            .inner();
+        assert!((closed - 500.0).abs() < 1.0, "{closed}");
+    }
+}
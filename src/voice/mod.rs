@@ -1,21 +1,72 @@
 use crate::{
-    midi::event::MidiEventListener,
-    modx::{env::EnvProps, fm, lfo::LfoProps, mod_pack::ModPack, ModValue},
+    fx::filter::Filter,
+    midi::{event::MidiEventListener, note::Note},
+    modx::{
+        env::EnvProps,
+        fm,
+        lfo::LfoProps,
+        matrix::{ModMatrix, ModSource},
+        mod_pack::{ModPack, ModTarget},
+        Modulate as _,
+    },
     osc::{
         clock::{Clock, Freq},
-        OpParams, OperatorPack, Osc,
+        OpParams, OpProps, OperatorPack, Osc,
     },
     param::f32::{SignedUnitInterval, UnitInterval},
-    sample::Frame,
+    sample::{time::SampleCount, Frame},
 };
+use filter::FilterProps;
+#[allow(unused)]
+use num_traits::Float as _;
 
 pub mod controller;
+pub mod filter;
 
 // TODO: Non-static osc props
 pub struct VoiceParams<'a, const OSCS: usize> {
     pub env_params: &'a [EnvProps],
     pub lfo_params: &'a [LfoProps],
-    pub amp_mod: Option<ModValue>,
+    pub matrix: &'a ModMatrix,
+    pub filter: &'a FilterProps,
+    /// Pitch offset of all voices in octaves, e.g. from pitch bend
+    pub pitch_bend: f32,
+    pub mod_wheel: UnitInterval,
+}
+
+/// Portamento between notes played by the same voice
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Glide {
+    #[default]
+    Off,
+    /// Glide takes the same time for any interval
+    Time(SampleCount),
+    /// Glide takes given time per octave
+    Rate(SampleCount),
+}
+
+impl Glide {
+    /// Glide duration in samples for given interval
+    #[inline]
+    pub fn duration(&self, octaves: f32) -> f32 {
+        match self {
+            Glide::Off => 0.0,
+            Glide::Time(time) => time.inner() as f32,
+            Glide::Rate(rate) => rate.inner() as f32 * octaves.abs(),
+        }
+    }
+}
+
+/// Lifecycle of a voice
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum VoiceState {
+    /// Silent, free to be taken and not ticked
+    #[default]
+    Idle,
+    /// Playing a note which is held
+    Held,
+    /// The note is released but envelopes are still sounding
+    Releasing,
 }
 
 // FIXME: Env changes how FM sounds with two oscs
@@ -24,11 +75,20 @@ pub struct VoiceParams<'a, const OSCS: usize> {
 pub struct Voice<O: Osc, const LFOS: usize, const ENVS: usize, const OSCS: usize> {
     ops: OperatorPack<O, OSCS>,
     root_freq: Freq,
+    /// Frequency of the played note, `root_freq` glides to it
+    target_freq: Freq,
+    /// Root frequency multiplier applied every tick while gliding
+    glide_factor: f32,
     detune: SignedUnitInterval,
     blend: UnitInterval,
     stereo_balance: UnitInterval,
+    /// Envelopes and LFOs triggered by notes this voice plays
     mods: ModPack<LFOS, ENVS, OSCS>,
     velocity: UnitInterval,
+    filter: Filter,
+    /// Amplitude of the last tick
+    level: UnitInterval,
+    state: VoiceState,
 }
 
 impl<O: Osc + 'static, const LFOS: usize, const ENVS: usize, const OSCS: usize> MidiEventListener
@@ -37,14 +97,17 @@ impl<O: Osc + 'static, const LFOS: usize, const ENVS: usize, const OSCS: usize>
     #[inline]
     fn note_on(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
         self.root_freq = note.freq();
-        self.velocity = velocity;
+        self.target_freq = note.freq();
+        self.glide_factor = 1.0;
 
-        self.mods.note_on(clock, note, velocity);
-        self.ops.note_on(clock, note, velocity);
+        self.retrigger(clock, note, velocity);
     }
 
     #[inline]
     fn note_off(&mut self, clock: &Clock, note: crate::midi::note::Note, velocity: UnitInterval) {
+        if self.state == VoiceState::Held {
+            self.state = VoiceState::Releasing;
+        }
         self.velocity = UnitInterval::MIN;
         self.mods.note_off(clock, note, velocity);
         self.ops.note_off(clock, note, velocity);
@@ -58,11 +121,16 @@ impl<O: Osc + 'static, const LFOS: usize, const ENVS: usize, const OSCS: usize>
         Self {
             ops: OperatorPack::new(osc),
             root_freq: Freq::ZERO,
+            target_freq: Freq::ZERO,
+            glide_factor: 1.0,
             detune: SignedUnitInterval::EQUILIBRIUM,
             blend: UnitInterval::MAX,
             stereo_balance: UnitInterval::EQUILIBRIUM,
             mods: ModPack::new(),
             velocity: UnitInterval::MIN,
+            filter: Filter::new(),
+            level: UnitInterval::MIN,
+            state: VoiceState::Idle,
         }
     }
 
@@ -72,6 +140,67 @@ impl<O: Osc + 'static, const LFOS: usize, const ENVS: usize, const OSCS: usize>
         self.detune = detune;
     }
 
+    /// Restart envelopes and LFOs keeping current pitch
+    #[inline]
+    pub fn retrigger(&mut self, clock: &Clock, note: Note, velocity: UnitInterval) {
+        if self.state == VoiceState::Idle {
+            self.filter.reset();
+        }
+        self.state = VoiceState::Held;
+        self.velocity = velocity;
+
+        self.mods.note_on(clock, note, velocity);
+        self.ops.note_on(clock, note, velocity);
+    }
+
+    /// Sweep the pitch from the current one to the note without retriggering
+    #[inline]
+    pub fn glide_to(&mut self, note: Note, glide: Glide) {
+        self.target_freq = note.freq();
+
+        let octaves = if self.root_freq.inner() > 0.0 {
+            (self.target_freq.inner() / self.root_freq.inner()).log2()
+        } else {
+            0.0
+        };
+        let duration = glide.duration(octaves);
+
+        if duration < 1.0 {
+            self.root_freq = self.target_freq;
+            self.glide_factor = 1.0;
+        } else {
+            self.glide_factor = 2f32.powf(octaves / duration);
+        }
+    }
+
+    #[inline]
+    fn advance_glide(&mut self) {
+        if self.glide_factor != 1.0 {
+            self.root_freq = self.root_freq * self.glide_factor;
+
+            if (self.glide_factor > 1.0) == (self.root_freq.inner() >= self.target_freq.inner()) {
+                self.root_freq = self.target_freq;
+                self.glide_factor = 1.0;
+            }
+        }
+    }
+
+    #[inline]
+    pub fn state(&self) -> VoiceState {
+        self.state
+    }
+
+    #[inline]
+    pub fn is_idle(&self) -> bool {
+        self.state == VoiceState::Idle
+    }
+
+    /// Amplitude the voice played at the last tick
+    #[inline]
+    pub fn level(&self) -> UnitInterval {
+        self.level
+    }
+
     #[inline]
     pub fn set_stereo_balance(&mut self, stereo_balance: UnitInterval) {
         self.stereo_balance = stereo_balance;
@@ -82,27 +211,68 @@ impl<O: Osc + 'static, const LFOS: usize, const ENVS: usize, const OSCS: usize>
         &mut self,
         clock: &Clock,
         params: &VoiceParams<'a, OSCS>,
-        op_params: &[OpParams<'static, O, OSCS>; OSCS],
+        op_props: &[OpProps<'static, O, OSCS>; OSCS],
     ) -> Frame {
+        if self.state == VoiceState::Idle {
+            return Frame::zero();
+        }
+
+        self.advance_glide();
+
         let freq = fm(self.root_freq, self.detune.inner());
 
-        let amp = self.blend
-            * params
-                .amp_mod
-                .map(|amp_mod| {
-                    match amp_mod {
-                        // // Use raw velocity without modulation
-                        // ModValue::None => self.velocity,
-                        // Envelope depends on velocity (attack goes to velocity), so env is just setting the amp.
-                        ModValue::Env(env) => env,
-                        // TODO: use `am` instead of mul?
-                        // Lfo modulates amp with max of given velocity
-                        ModValue::Lfo(lfo) => lfo.remap_into_ui() * self.velocity,
-                    }
-                })
-                .unwrap_or(UnitInterval::MAX);
-
-        let sample = self.ops.tick(clock, freq, op_params) * amp.inner();
+        let sources = self.mods.tick(
+            clock,
+            params.lfo_params,
+            params.env_params,
+            params.mod_wheel,
+        );
+
+        // Released voice is over when all its envelopes are finished, without envelopes it's gated by the note right away
+        if self.state == VoiceState::Releasing && sources.envs.iter().all(Option::is_none) {
+            self.state = VoiceState::Idle;
+            self.level = UnitInterval::MIN;
+            return Frame::zero();
+        }
+        let mut modulation = |target: ModTarget| params.matrix.modulation(target, &sources);
+
+        let pitch_mod = match modulation(ModTarget::GlobalPitch) {
+            Some(pitch_mod) => Some(pitch_mod + params.pitch_bend),
+            None if params.pitch_bend != 0.0 => Some(params.pitch_bend),
+            None => None,
+        };
+
+        // Note: Need array allocation because we cannot pass slice (params are modulated) and don't want a vector
+        let op_params: [OpParams<'static, O, OSCS>; OSCS] =
+            core::array::from_fn(|index| OpParams {
+                props: op_props[index].modulated(&mut modulation),
+                pitch_mod,
+            });
+
+        let filter_cutoff_mod = modulation(ModTarget::FilterCutoff);
+        let filter_resonance_mod = modulation(ModTarget::FilterResonance);
+
+        let amp = self.blend * params.matrix.level(&sources);
+        self.level = amp;
+
+        let sample = self.ops.tick(clock, freq, &op_params);
+
+        let sample = if params.filter.enabled {
+            self.filter.process(
+                clock.sample_rate,
+                sample,
+                &params.filter.params(
+                    self.root_freq,
+                    sources
+                        .get(ModSource::Env(params.filter.env))
+                        .map(UnitInterval::new),
+                    filter_cutoff_mod,
+                    filter_resonance_mod,
+                ),
+            )
+        } else {
+            sample
+        } * amp.inner();
 
         Frame::mono(sample).stereo_balanced(self.stereo_balance)
     }
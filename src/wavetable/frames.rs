@@ -0,0 +1,180 @@
+use super::{spectral::Spectrum, Wavetable, WavetableRow};
+use crate::import::wav::{WavReadError, WavReader};
+use alloc::{boxed::Box, vec::Vec};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WavetableLoadError {
+    Wav(WavReadError),
+    /// Cycle length of zero
+    InvalidCycleLen,
+    /// The file is shorter than a single cycle
+    NoFrames,
+}
+
+impl From<WavReadError> for WavetableLoadError {
+    #[inline]
+    fn from(value: WavReadError) -> Self {
+        Self::Wav(value)
+    }
+}
+
+/// Single-cycle frames of a wavetable file resampled to `LENGTH`. Count of frames is only known at runtime, so frames live on the heap until the table is built with [`WavetableFrames::wavetable`].
+#[derive(Debug, Clone)]
+pub struct WavetableFrames<const LENGTH: usize> {
+    frames: Vec<[f32; LENGTH]>,
+}
+
+impl<const LENGTH: usize> WavetableFrames<LENGTH> {
+    /// Slice a mono WAV file into consecutive frames of `cycle_len` samples. The cycle length of the Serum `clm ` chunk overrides `cycle_len`, 2048 is the common one for files without it. Incomplete trailing frame is dropped.
+    pub fn from_wav(bytes: &[u8], cycle_len: usize) -> Result<Self, WavetableLoadError> {
+        let reader = WavReader::new(bytes)?;
+        let cycle_len = reader.cycle_len().unwrap_or(cycle_len);
+
+        if cycle_len == 0 {
+            return Err(WavetableLoadError::InvalidCycleLen);
+        }
+
+        let samples = reader.samples().collect::<Vec<_>>();
+        let frames = samples
+            .chunks_exact(cycle_len)
+            .map(Self::resample)
+            .collect::<Vec<_>>();
+
+        if frames.is_empty() {
+            return Err(WavetableLoadError::NoFrames);
+        }
+
+        Ok(Self { frames })
+    }
+
+    /// Resample a single cycle by its spectrum, see [`Spectrum::from_cycle`]
+    fn resample(cycle: &[f32]) -> [f32; LENGTH] {
+        Spectrum::<LENGTH>::from_cycle(cycle).to_samples()
+    }
+
+    /// Count of frames
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    #[inline]
+    pub fn frame(&self, index: usize) -> &[f32; LENGTH] {
+        &self.frames[index]
+    }
+
+    /// Band-limited row of a frame, see [`WavetableRow::from_samples`]
+    #[inline]
+    pub fn row(&self, index: usize) -> WavetableRow<LENGTH> {
+        WavetableRow::from_samples(&self.frames[index])
+    }
+
+    /// Table of `DEPTH` rows picked evenly from the first to the last frame. Rows are repeated if there are less frames than `DEPTH`. Samples of rows are on the heap, so only the row handles are built on the stack.
+    pub fn wavetable<const DEPTH: usize>(&self) -> Box<Wavetable<DEPTH, LENGTH>> {
+        let last = self.len() - 1;
+
+        Box::new(Wavetable::from_rows(core::array::from_fn(|depth| {
+            let index = if DEPTH > 1 {
+                (depth * last + (DEPTH - 1) / 2) / (DEPTH - 1)
+            } else {
+                0
+            };
+            self.row(index)
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WavetableFrames, WavetableLoadError};
+    use crate::import::wav::mono_float_wav;
+    use alloc::vec::Vec;
+    use core::f32::consts::TAU;
+    #[allow(unused)]
+    use num_traits::Float as _;
+
+    #[test]
+    fn serum_frames() {
+        // Sines of growing amplitude, 4 cycles of 256 samples and a partial one
+        let samples = (0..4 * 256 + 100)
+            .map(|index| (TAU * index as f32 / 256.0).sin() * ((index / 256) + 1) as f32 / 4.0)
+            .collect::<Vec<_>>();
+
+        // Cycle length of the `clm ` chunk wins
+        let frames =
+            WavetableFrames::<128>::from_wav(&mono_float_wav(48_000, Some(256), &samples), 512)
+                .unwrap();
+        assert_eq!(frames.len(), 4);
+
+        (0..4).for_each(|frame| {
+            frames
+                .frame(frame)
+                .iter()
+                .enumerate()
+                .for_each(|(index, sample)| {
+                    let expected = (TAU * index as f32 / 128.0).sin() * (frame + 1) as f32 / 4.0;
+                    assert!((sample - expected).abs() < 1e-5, "{frame}: {index}");
+                });
+        });
+
+        let wavetable = frames.wavetable::<2>();
+        assert!((wavetable.at(0, 0.25, 0.0) - 0.25).abs() < 1e-5);
+        assert!((wavetable.at(1, 0.25, 0.0) - 1.0).abs() < 1e-5);
+
+        // Without the chunk the given cycle length is used, upsampling the frames
+        let frames =
+            WavetableFrames::<1024>::from_wav(&mono_float_wav(48_000, None, &samples), 512)
+                .unwrap();
+        assert_eq!(frames.len(), 2);
+        assert!((frames.frame(1)[128] - 0.75).abs() < 1e-5);
+
+        assert_eq!(
+            WavetableFrames::<128>::from_wav(&mono_float_wav(48_000, None, &samples), 2048)
+                .unwrap_err(),
+            WavetableLoadError::NoFrames
+        );
+    }
+
+    #[test]
+    fn serum_sized_table() {
+        // 64 frames of 2048 samples, sines of growing amplitude with a partial above Nyquist of the table rows
+        let samples = (0..64 * 2048)
+            .map(|index| {
+                let phase = (index % 2048) as f32 / 2048.0;
+                (TAU * phase).sin() * ((index / 2048) + 1) as f32 / 64.0
+                    + 0.1 * (TAU * 600.0 * phase).sin()
+            })
+            .collect::<Vec<_>>();
+
+        let frames =
+            WavetableFrames::<1024>::from_wav(&mono_float_wav(48_000, Some(2048), &samples), 2048)
+                .unwrap();
+        assert_eq!(frames.len(), 64);
+
+        // The partial is dropped instead of folding to harmonic 424
+        (0..64).for_each(|frame| {
+            frames
+                .frame(frame)
+                .iter()
+                .enumerate()
+                .for_each(|(index, sample)| {
+                    let expected = (TAU * index as f32 / 1024.0).sin() * (frame + 1) as f32 / 64.0;
+                    assert!((sample - expected).abs() < 1e-4, "{frame}: {index}");
+                });
+        });
+
+        let wavetable = frames.wavetable::<64>();
+        (0..64).for_each(|depth| {
+            let peak = wavetable.at(depth, 0.25, 0.0);
+            assert!(
+                (peak - (depth + 1) as f32 / 64.0).abs() < 1e-4,
+                "{depth}: {peak}"
+            );
+        });
+    }
+}
@@ -1,9 +1,12 @@
 use num_traits::Float;
 
-use crate::modx::{ModValue, Modulate};
+use crate::modx::Modulate;
+use alloc::boxed::Box;
 // use micromath::F32Ext;
 
+pub mod frames;
 pub mod osc;
+pub mod spectral;
 pub mod synth;
 
 // /// The minimum amount of deviation from integer for lerp to be applied to neighbor samples
@@ -11,45 +14,116 @@ pub mod synth;
 
 pub const WAVETABLE_DEPTH_LERP_THRESHOLD: f32 = 0.001;
 
-// /// Note: Static assertions are impossible in current stable to check LENGTH. LENGTH MUST BE a power of two for modulo optimization
-#[derive(Debug)]
+/// The minimum weight of the narrower mip level for levels to be crossfaded
+pub const WAVETABLE_MIP_LERP_THRESHOLD: f32 = 0.001;
+
+/// Samples per cycle of the highest harmonic kept in a band-limited level. Levels longer than the row are capped to its length, narrower levels are shorter so that their storage halves with each level.
+pub const WAVETABLE_MIP_OVERSAMPLING: usize = 16;
+
+/// Single cycle with its band-limited mip levels, `LENGTH` must be a power of two for the FFT which is checked at compile time. Levels live on the heap and take less than `(log2(WAVETABLE_MIP_OVERSAMPLING) + 1) * LENGTH` samples, about 20 KiB for `LENGTH` of 1024.
+#[derive(Debug, Clone)]
 pub struct WavetableRow<const LENGTH: usize> {
-    samples: [f32; LENGTH],
+    /// Level `n` keeps harmonics up to `LENGTH / 2^(n + 1)`, the first level is the row as generated. See [`WavetableRow::level_len`].
+    levels: Box<[Box<[f32]>]>,
 }
 
 impl<const LENGTH: usize> WavetableRow<LENGTH> {
     const LENGTH_F: f32 = LENGTH as f32;
+    /// Evaluated by constructors, so that a table of wrong length fails to compile instead of panicking in the FFT
+    const POWER_OF_TWO: () = assert!(
+        LENGTH.is_power_of_two(),
+        "Wavetable row length must be a power of two"
+    );
+    /// Count of mip levels, the last one is a sine of the fundamental
+    pub const LEVELS: usize = LENGTH.trailing_zeros() as usize;
 
     pub fn new(f: impl Fn(f32) -> f32) -> Self {
-        Self {
-            samples: core::array::from_fn(|index| {
-                let phase = index as f32 / Self::LENGTH_F;
-                f(phase)
-            }),
+        Self::from_samples(&core::array::from_fn(|index| {
+            let phase = index as f32 / Self::LENGTH_F;
+            f(phase)
+        }))
+    }
+
+    /// Build band-limited levels of a single cycle by truncating its harmonics
+    pub fn from_samples(samples: &[f32; LENGTH]) -> Self {
+        let () = Self::POWER_OF_TWO;
+        let spectrum = spectral::Spectrum::<LENGTH>::from_samples(samples);
+
+        let levels = (0..Self::LEVELS)
+            .map(|level| {
+                if level == 0 {
+                    Box::from(&samples[..])
+                } else {
+                    let mut spectrum = spectrum;
+                    spectrum.truncate(LENGTH >> (level + 1));
+
+                    // Harmonics are below Nyquist of the shorter level, so it's just decimated
+                    spectrum
+                        .to_samples()
+                        .iter()
+                        .step_by(LENGTH / Self::level_len(level))
+                        .copied()
+                        .collect()
+                }
+            })
+            .collect();
+
+        Self { levels }
+    }
+
+    /// Samples of the level, [`WAVETABLE_MIP_OVERSAMPLING`] times its highest harmonic up to `LENGTH`
+    #[inline]
+    pub const fn level_len(level: usize) -> usize {
+        if level == 0 {
+            LENGTH
+        } else {
+            let len = (LENGTH >> (level + 1)) * WAVETABLE_MIP_OVERSAMPLING;
+            if len < LENGTH {
+                len
+            } else {
+                LENGTH
+            }
         }
     }
 
-    // #[inline(always)]
-    pub fn lerp(&self, phase: f32) -> f32 {
+    /// Band-limited level position for a phase step. Each level is free of aliasing up to the next integer position, so neighbor levels are crossfaded.
+    #[inline]
+    fn mip_position(phase_step: f32) -> f32 {
+        ((phase_step * Self::LENGTH_F).log2() + 1.0).clamp(0.0, (Self::LEVELS - 1) as f32)
+    }
+
+    #[inline]
+    fn level_lerp(&self, level: usize, phase: f32) -> f32 {
+        let samples = &self.levels[level];
+        let len = samples.len();
+
         // FIXME: phase of 1.0 * LENGTH is max size, but phase is never 1.0, will it happen?
-        let index = phase * Self::LENGTH_F;
+        let index = phase * len as f32;
         let left_index = index as usize;
         // // Note: Modulo optimization, x % LENGTH == x % (LENGTH - 1) for LENGTH being a power of two
         // let right_index = (left_index + 1) & (LENGTH - 1);
 
-        let right_index = (left_index + 1) % LENGTH;
+        let right_index = (left_index + 1) % len;
 
         let right_index_factor = index.fract();
         let left_index_factor = 1.0 - right_index_factor;
 
-        // if right_index_factor > WAVETABLE_LERP_DELTA_THRESHOLD {
-        //     self.samples[right_index]
-        // } else if left_index_factor > WAVETABLE_LERP_DELTA_THRESHOLD {
-        //     self.samples[left_index]
-        // } else {
-        self.samples[left_index] * left_index_factor
-            + self.samples[right_index] * right_index_factor
-        // }
+        samples[left_index] * left_index_factor + samples[right_index] * right_index_factor
+    }
+
+    /// Sample at `phase` of the levels band-limited for the phase step of the oscillator. Phase step of 0.0 reads the row as generated.
+    // #[inline(always)]
+    pub fn lerp(&self, phase: f32, phase_step: f32) -> f32 {
+        let position = Self::mip_position(phase_step);
+        let level = position as usize;
+        let upper_factor = position.fract();
+
+        if upper_factor > WAVETABLE_MIP_LERP_THRESHOLD && level + 1 < Self::LEVELS {
+            self.level_lerp(level, phase) * (1.0 - upper_factor)
+                + self.level_lerp(level + 1, phase) * upper_factor
+        } else {
+            self.level_lerp(level, phase)
+        }
     }
 }
 
@@ -64,10 +138,10 @@ impl<const DEPTH: usize, const LENGTH: usize> Wavetable<DEPTH, LENGTH> {
     }
 
     #[inline(always)]
-    pub fn at(&self, depth: usize, phase: f32) -> f32 {
+    pub fn at(&self, depth: usize, phase: f32, phase_step: f32) -> f32 {
         // debug_assert!(phase >= 0.0 && phase < 1.0, "Malformed phase {phase}");
         // unsafe { self.rows.get_unchecked(depth).lerp(phase) }
-        self.rows[depth % DEPTH].lerp(phase)
+        self.rows[depth % DEPTH].lerp(phase, phase_step)
     }
 }
 
@@ -95,13 +169,13 @@ impl<'a, const DEPTH: usize, const LENGTH: usize> Modulate for WavetableProps<'a
     #[inline]
     fn modulated(
         &self,
-        mut f: impl FnMut(crate::modx::mod_pack::ModTarget) -> Option<ModValue>,
+        mut f: impl FnMut(crate::modx::mod_pack::ModTarget) -> Option<f32>,
     ) -> Self {
         if let Some(depth_mod) = f(crate::modx::mod_pack::ModTarget::OscWtPos(self.osc_index)) {
-            let depth_offset = depth_mod.as_sui();
+            let depth_offset = depth_mod.clamp(-1.0, 1.0);
 
-            let depth = (Self::DEPTH_F + self.depth as f32 + Self::DEPTH_F * depth_offset.inner())
-                % Self::DEPTH_F;
+            let depth =
+                (Self::DEPTH_F + self.depth as f32 + Self::DEPTH_F * depth_offset) % Self::DEPTH_F;
             let left_depth = depth as usize;
 
             let right_depth_factor = depth.fract();
@@ -123,7 +197,7 @@ impl<'a, const DEPTH: usize, const LENGTH: usize> crate::param::ui::EguiComponen
     for WavetableProps<'a, DEPTH, LENGTH>
 {
     fn egui(&mut self, ui: &mut egui::Ui, _params: crate::param::ui::DefaultUiParams) {
-        crate::param::ui::egui_wave(ui, |x| self.lerp(x));
+        crate::param::ui::egui_wave(ui, |x| self.lerp(x, 0.0));
 
         ui.add(
             egui::Slider::from_get_set(0.0..=DEPTH as f64 - 1.0, |new_value| {
@@ -149,13 +223,14 @@ impl<'a, const DEPTH: usize, const LENGTH: usize> WavetableProps<'a, DEPTH, LENG
         }
     }
 
+    /// Sample at `phase` band-limited for `phase_step` of the oscillator
     // #[inline(always)]
-    pub fn lerp(&self, phase: f32) -> f32 {
+    pub fn lerp(&self, phase: f32, phase_step: f32) -> f32 {
         if let Some((left_depth_factor, right_depth_factor)) = self.depth_lerp {
-            self.wavetable.at(self.depth, phase) * left_depth_factor
-                + self.wavetable.at(self.depth + 1, phase) * right_depth_factor
+            self.wavetable.at(self.depth, phase, phase_step) * left_depth_factor
+                + self.wavetable.at(self.depth + 1, phase, phase_step) * right_depth_factor
         } else {
-            self.wavetable.at(self.depth, phase)
+            self.wavetable.at(self.depth, phase, phase_step)
         }
     }
 
@@ -166,3 +241,43 @@ impl<'a, const DEPTH: usize, const LENGTH: usize> WavetableProps<'a, DEPTH, LENG
     //     }
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::WavetableRow;
+    use crate::{midi::note::Note, osc::aliasing};
+    use alloc::vec::Vec;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+    /// 55 cycles of A7, each harmonic falls exactly on a DFT bin
+    const LEN: usize = 750;
+
+    #[test]
+    fn mip_levels_saw_a7() {
+        let saw = WavetableRow::<1024>::new(|phase| 2.0 * (phase - (phase + 0.5).floor()));
+        let freq = Note::A7.freq().inner();
+        let phase_step = freq / SAMPLE_RATE;
+
+        let render = |mip_phase_step: f32| {
+            (0..LEN)
+                .map(|index| saw.lerp((index as f32 * phase_step).fract(), mip_phase_step))
+                .collect::<Vec<_>>()
+        };
+        let cycles = (freq * LEN as f32 / SAMPLE_RATE) as usize;
+
+        // Zero phase step reads the row as generated
+        let naive = aliasing(&render(0.0), cycles);
+        let band_limited = aliasing(&render(phase_step), cycles);
+
+        assert!(band_limited < 1e-4, "{band_limited} vs {naive}");
+        assert!(band_limited < naive / 100.0, "{band_limited} vs {naive}");
+
+        // 4 full length levels, narrower ones halve down to 16 samples of the fundamental
+        type Row = WavetableRow<1024>;
+        assert_eq!(Row::LEVELS, 10);
+        assert_eq!(
+            (0..Row::LEVELS).map(Row::level_len).collect::<Vec<_>>(),
+            [1024, 1024, 1024, 1024, 512, 256, 128, 64, 32, 16]
+        );
+    }
+}
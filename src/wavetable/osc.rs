@@ -21,7 +21,7 @@ impl<const DEPTH: usize, const LENGTH: usize> Osc for WavetableOsc<DEPTH, LENGTH
     type Props<'a> = WavetableProps<'a, DEPTH, LENGTH>;
 
     #[inline(always)]
-    fn tick<'a>(&mut self, phase: f32, params: &Self::Props<'a>) -> f32 {
-        params.lerp(phase)
+    fn tick<'a>(&mut self, phase: f32, phase_step: f32, params: &Self::Props<'a>) -> f32 {
+        params.lerp(phase, phase_step)
     }
 }
@@ -0,0 +1,368 @@
+use super::{Wavetable, WavetableRow};
+use alloc::vec::Vec;
+use core::f32::consts::{FRAC_PI_2, TAU};
+use num::complex::Complex32;
+#[allow(unused)]
+use num_traits::Float as _;
+
+/// Harmonics quieter than this have no meaningful phase, morphing takes the phase of the other keyframe
+pub const SPECTRAL_SILENT_AMP: f32 = 1e-6;
+
+/// In-place radix-2 FFT, the length of `bins` must be a power of two
+pub fn fft(bins: &mut [Complex32]) {
+    transform(bins, -1.0);
+}
+
+/// In-place inverse of [`fft`], scaled so that `ifft(fft(x)) == x`
+pub fn ifft(bins: &mut [Complex32]) {
+    transform(bins, 1.0);
+
+    let scale = 1.0 / bins.len() as f32;
+    bins.iter_mut().for_each(|bin| *bin *= scale);
+}
+
+fn transform(bins: &mut [Complex32], sign: f32) {
+    let len = bins.len();
+    assert!(
+        len.is_power_of_two(),
+        "FFT length {len} is not a power of two"
+    );
+
+    if len == 1 {
+        return;
+    }
+
+    let bits = len.trailing_zeros();
+    (0..len).for_each(|index| {
+        let reversed = index.reverse_bits() >> (usize::BITS - bits);
+        if reversed > index {
+            bins.swap(index, reversed);
+        }
+    });
+
+    let mut size = 2;
+    while size <= len {
+        let half = size / 2;
+        let angle = sign * TAU / size as f32;
+
+        bins.chunks_exact_mut(size).for_each(|chunk| {
+            (0..half).for_each(|index| {
+                let (sin, cos) = (angle * index as f32).sin_cos();
+                let even = chunk[index];
+                let odd = chunk[index + half] * Complex32::new(cos, sin);
+
+                chunk[index] = even + odd;
+                chunk[index + half] = even - odd;
+            });
+        });
+
+        size *= 2;
+    }
+}
+
+/// Amplitude and phase of a sine partial, `amp * sin(TAU * k * phase + phase_offset)` for harmonic `k`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Harmonic {
+    pub amp: f32,
+    pub phase: f32,
+}
+
+impl Harmonic {
+    #[inline]
+    pub const fn new(amp: f32, phase: f32) -> Self {
+        Self { amp, phase }
+    }
+
+    /// Magnitude lerp with the phase moving along the shorter arc
+    #[inline]
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        let phase = if self.amp < SPECTRAL_SILENT_AMP {
+            other.phase
+        } else if other.amp < SPECTRAL_SILENT_AMP {
+            self.phase
+        } else {
+            let delta = other.phase - self.phase;
+            self.phase + t * (delta - TAU * (delta / TAU).round())
+        };
+
+        Self {
+            amp: self.amp + t * (other.amp - self.amp),
+            phase,
+        }
+    }
+}
+
+/// Spectrum of a single cycle of `LENGTH` samples, `LENGTH` must be a power of two which is checked at compile time. Harmonics go from the fundamental `1` up to `LENGTH / 2`.
+#[derive(Debug, Clone, Copy)]
+pub struct Spectrum<const LENGTH: usize> {
+    /// FFT bins, only the lower half up to Nyquist is kept as the upper one mirrors it for real signals
+    bins: [Complex32; LENGTH],
+}
+
+impl<const LENGTH: usize> Spectrum<LENGTH> {
+    const NYQUIST: usize = LENGTH / 2;
+    /// Evaluated by constructors, see [`WavetableRow`]
+    const POWER_OF_TWO: () = assert!(
+        LENGTH.is_power_of_two(),
+        "Spectrum length must be a power of two"
+    );
+
+    pub fn from_samples(samples: &[f32; LENGTH]) -> Self {
+        let () = Self::POWER_OF_TWO;
+        let mut bins = samples.map(|sample| Complex32::new(sample, 0.0));
+        fft(&mut bins);
+
+        let mut spectrum = Self { bins };
+        spectrum.truncate(Self::NYQUIST);
+        spectrum
+    }
+
+    /// Additive spectrum, `amps[0]` and `phases[0]` are of the fundamental. Missing phases are zero, harmonics above Nyquist are dropped.
+    pub fn from_harmonics(amps: &[f32], phases: &[f32]) -> Self {
+        let () = Self::POWER_OF_TWO;
+        let mut spectrum = Self {
+            bins: [Complex32::new(0.0, 0.0); LENGTH],
+        };
+
+        amps.iter()
+            .enumerate()
+            .take(Self::NYQUIST)
+            .for_each(|(index, &amp)| {
+                let phase = phases.get(index).copied().unwrap_or(0.0);
+                spectrum.set_harmonic(index + 1, Harmonic::new(amp, phase));
+            });
+
+        spectrum
+    }
+
+    /// Spectrum of a single cycle of any length, harmonics above `LENGTH / 2` are dropped instead of folding. Cycles of a length which isn't a power of two are transformed by a direct DFT, which is slow but is only done on loading.
+    pub fn from_cycle(cycle: &[f32]) -> Self {
+        let () = Self::POWER_OF_TWO;
+        let len = cycle.len();
+        let harmonics = (len / 2).min(Self::NYQUIST);
+
+        let bins = if len.is_power_of_two() {
+            let mut bins = cycle
+                .iter()
+                .map(|&sample| Complex32::new(sample, 0.0))
+                .collect::<Vec<_>>();
+            fft(&mut bins);
+            bins
+        } else {
+            let twiddles = (0..len)
+                .map(|index| {
+                    let (sin, cos) = (-TAU * index as f32 / len as f32).sin_cos();
+                    Complex32::new(cos, sin)
+                })
+                .collect::<Vec<_>>();
+
+            (0..=harmonics)
+                .map(|harmonic| {
+                    cycle
+                        .iter()
+                        .enumerate()
+                        .map(|(index, &sample)| twiddles[harmonic * index % len] * sample)
+                        .sum()
+                })
+                .collect()
+        };
+
+        let mut spectrum = Self {
+            bins: [Complex32::new(0.0, 0.0); LENGTH],
+        };
+        spectrum.set_dc(bins[0].re / len as f32);
+        (1..=harmonics).for_each(|harmonic| {
+            spectrum.set_harmonic(harmonic, bin_harmonic(bins[harmonic], harmonic, len));
+        });
+
+        spectrum
+    }
+
+    #[inline]
+    pub fn dc(&self) -> f32 {
+        self.bins[0].re / LENGTH as f32
+    }
+
+    #[inline]
+    pub fn set_dc(&mut self, dc: f32) {
+        self.bins[0] = Complex32::new(dc * LENGTH as f32, 0.0);
+    }
+
+    /// Partial of harmonic `harmonic` in `1..=LENGTH / 2`
+    #[inline]
+    pub fn harmonic(&self, harmonic: usize) -> Harmonic {
+        bin_harmonic(self.bins[harmonic], harmonic, LENGTH)
+    }
+
+    #[inline]
+    pub fn set_harmonic(&mut self, harmonic: usize, partial: Harmonic) {
+        let scale = if harmonic == Self::NYQUIST { 1.0 } else { 0.5 } * LENGTH as f32;
+        let (sin, cos) = (partial.phase - FRAC_PI_2).sin_cos();
+
+        self.bins[harmonic] = Complex32::new(cos, sin) * partial.amp * scale;
+    }
+
+    /// Remove harmonics above `harmonics`
+    #[inline]
+    pub fn truncate(&mut self, harmonics: usize) {
+        self.bins
+            .iter_mut()
+            .skip(harmonics + 1)
+            .for_each(|bin| *bin = Complex32::new(0.0, 0.0));
+    }
+
+    /// Spectral morph, partials are interpolated by [`Harmonic::lerp`] so that intermediate frames keep their loudness instead of cancelling out as in a crossfade
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mut spectrum = *self;
+        spectrum.set_dc(self.dc() + t * (other.dc() - self.dc()));
+
+        (1..=Self::NYQUIST).for_each(|harmonic| {
+            let partial = self.harmonic(harmonic).lerp(other.harmonic(harmonic), t);
+            spectrum.set_harmonic(harmonic, partial);
+        });
+
+        spectrum
+    }
+
+    pub fn to_samples(&self) -> [f32; LENGTH] {
+        let mut bins = self.bins;
+        (Self::NYQUIST + 1..LENGTH).for_each(|index| bins[index] = bins[LENGTH - index].conj());
+        ifft(&mut bins);
+
+        bins.map(|bin| bin.re)
+    }
+}
+
+/// Partial of FFT `bin` of `harmonic` in a transform of `len` samples
+#[inline]
+fn bin_harmonic(bin: Complex32, harmonic: usize, len: usize) -> Harmonic {
+    let scale = if 2 * harmonic == len { 1.0 } else { 2.0 } / len as f32;
+
+    // Sine is the cosine delayed by a quarter of the cycle
+    Harmonic::new(
+        bin.norm_sqr().sqrt() * scale,
+        bin.im.atan2(bin.re) + FRAC_PI_2,
+    )
+}
+
+impl<const LENGTH: usize> WavetableRow<LENGTH> {
+    /// Additive row, see [`Spectrum::from_harmonics`]
+    pub fn from_harmonics(amps: &[f32], phases: &[f32]) -> Self {
+        Self::from_samples(&Spectrum::<LENGTH>::from_harmonics(amps, phases).to_samples())
+    }
+}
+
+impl<const DEPTH: usize, const LENGTH: usize> Wavetable<DEPTH, LENGTH> {
+    /// Table with keyframes at their depths and rows in between spectrally morphed by [`Spectrum::lerp`]. Keyframes must be sorted by depth, rows before the first and after the last keyframe repeat it.
+    pub fn from_keyframes(keyframes: &[(usize, [f32; LENGTH])]) -> Self {
+        assert!(!keyframes.is_empty(), "Wavetable needs a keyframe");
+        debug_assert!(
+            keyframes.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "Keyframes are not sorted by depth"
+        );
+
+        let spectra = keyframes
+            .iter()
+            .map(|(depth, samples)| (*depth, Spectrum::from_samples(samples)))
+            .collect::<Vec<_>>();
+
+        Self::from_rows(core::array::from_fn(|depth| {
+            let next = spectra.partition_point(|(key_depth, _)| *key_depth <= depth);
+
+            let samples = match (spectra.get(next.wrapping_sub(1)), spectra.get(next)) {
+                (Some((left_depth, left)), Some((right_depth, right))) => {
+                    let t = (depth - left_depth) as f32 / (right_depth - left_depth) as f32;
+                    left.lerp(right, t).to_samples()
+                }
+                (Some((_, spectrum)), None) | (None, Some((_, spectrum))) => spectrum.to_samples(),
+                (None, None) => unreachable!(),
+            };
+
+            WavetableRow::from_samples(&samples)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Harmonic, Spectrum};
+    use crate::wavetable::Wavetable;
+    use alloc::vec::Vec;
+    use core::f32::consts::{FRAC_PI_2, FRAC_PI_4, TAU};
+    #[allow(unused)]
+    use num_traits::Float as _;
+
+    const LENGTH: usize = 256;
+
+    #[test]
+    fn additive_and_morph() {
+        // Saw of 1/k partials
+        let amps: [f32; 32] = core::array::from_fn(|index| 1.0 / (index + 1) as f32);
+        let saw = Spectrum::<LENGTH>::from_harmonics(&amps, &[]).to_samples();
+        let spectrum = Spectrum::from_samples(&saw);
+        (1..=32).for_each(|harmonic| {
+            let partial = spectrum.harmonic(harmonic);
+            assert!((partial.amp - 1.0 / harmonic as f32).abs() < 1e-5);
+            assert!(partial.phase.abs() < 1e-3, "{harmonic}: {partial:?}");
+        });
+        assert!(spectrum.harmonic(33).amp < 1e-5);
+
+        // Morphing a sine into its copy a quarter cycle later keeps the amplitude, crossfade would drop it by 3 dB
+        let sine = Spectrum::<LENGTH>::from_harmonics(&[1.0], &[0.0]);
+        let cosine = Spectrum::<LENGTH>::from_harmonics(&[1.0], &[FRAC_PI_2]);
+        let middle = sine.lerp(&cosine, 0.5);
+        assert!((middle.harmonic(1).amp - 1.0).abs() < 1e-5);
+        assert!((middle.harmonic(1).phase - FRAC_PI_4).abs() < 1e-5);
+        let peak = middle.to_samples().into_iter().fold(0.0, f32::max);
+        assert!((peak - 1.0).abs() < 1e-3, "{peak}");
+
+        // Phase goes along the shorter arc over the wrap
+        let partial = Harmonic::new(1.0, 0.1).lerp(Harmonic::new(1.0, TAU - 0.1), 0.5);
+        assert!(partial.phase.abs() < 1e-5, "{partial:?}");
+
+        let wavetable = Wavetable::<5, LENGTH>::from_keyframes(&[
+            (1, sine.to_samples()),
+            (3, cosine.to_samples()),
+        ]);
+        [0, 1].into_iter().for_each(|depth| {
+            assert!((wavetable.at(depth, 0.25, 0.0) - 1.0).abs() < 1e-5);
+        });
+        assert!((wavetable.at(2, 0.125, 0.0) - 1.0).abs() < 1e-3);
+        [3, 4].into_iter().for_each(|depth| {
+            assert!(wavetable.at(depth, 0.25, 0.0).abs() < 1e-5);
+        });
+    }
+
+    #[test]
+    fn cycle_of_any_length() {
+        // 600 samples with a partial close to their Nyquist
+        let cycle = (0..600)
+            .map(|index| {
+                let phase = index as f32 / 600.0;
+                (TAU * phase).sin() + 0.5 * (TAU * 250.0 * phase).sin()
+            })
+            .collect::<Vec<_>>();
+
+        // Partials are kept exactly without images of interpolation
+        let spectrum = Spectrum::<1024>::from_cycle(&cycle);
+        assert!((spectrum.harmonic(1).amp - 1.0).abs() < 1e-4);
+        assert!((spectrum.harmonic(250).amp - 0.5).abs() < 1e-4);
+        assert!(spectrum.harmonic(350).amp < 1e-4);
+        spectrum
+            .to_samples()
+            .iter()
+            .enumerate()
+            .for_each(|(index, sample)| {
+                let phase = index as f32 / 1024.0;
+                let expected = (TAU * phase).sin() + 0.5 * (TAU * 250.0 * phase).sin();
+                assert!((sample - expected).abs() < 1e-3, "{index}: {sample}");
+            });
+
+        // The partial above Nyquist of a shorter length is dropped
+        let samples = Spectrum::<256>::from_cycle(&cycle).to_samples();
+        samples.iter().enumerate().for_each(|(index, sample)| {
+            let expected = (TAU * index as f32 / 256.0).sin();
+            assert!((sample - expected).abs() < 1e-3, "{index}: {sample}");
+        });
+    }
+}